@@ -0,0 +1,302 @@
+//! The `lexer!` macro, which builds a lexer from token rules at compile time.
+//!
+//! ```ignore
+//! mod calc {
+//!     rlex_macros::lexer! {
+//!         Ident = "(a-z)+ (a-z | 0-9 | _)*";
+//!         Number = "0-9+";
+//!         _ = r#" " "+ "#;
+//!     }
+//! }
+//! ```
+//!
+//! Every rule names a token and gives its pattern, in the regex syntax of
+//! rlex, as a string literal. Rules named `_` skip the text they match. The
+//! macro expands to the same items as the table-driven lexer rlex generates
+//! for a spec with those rules: a `Token` enum, `Lexeme`, `LexError` and a
+//! `Lexer<'a>` iterator, so it is best invoked in a module of its own.
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use rlex::codegen;
+use rlex::fsa::{LexerDfa, LexerNfa};
+use rlex::spec::{is_keyword, Action, Rule, SpecErrorKind};
+use rlex::{Diagnostic, Span as SourceSpan, Spec};
+
+#[proc_macro]
+pub fn lexer(input: TokenStream) -> TokenStream {
+    let rules = match parse_rules(input) {
+        Ok(rules) => rules,
+        Err((span, message)) => return compile_error(span, &message),
+    };
+
+    let names: Vec<(String, String)> = rules
+        .iter()
+        .map(|rule| (rule.name.clone(), rule.pattern.clone()))
+        .collect();
+
+    match expand(&names) {
+        Ok(code) => code.parse().unwrap(),
+        Err((rule, message)) => compile_error(rules[rule].span, &message),
+    }
+}
+
+/// A rule as written in the macro input.
+struct MacroRule {
+    name: String,
+    pattern: String,
+    // The span of the pattern literal.
+    span: Span,
+}
+
+/// Reads `Name = "pattern";` rules, reporting the span of the first token
+/// that does not fit.
+fn parse_rules(input: TokenStream) -> Result<Vec<MacroRule>, (Span, String)> {
+    let mut tokens = input.into_iter().peekable();
+    let mut rules = vec![];
+
+    while let Some(token) = tokens.next() {
+        let name = match &token {
+            TokenTree::Ident(ident) => {
+                let name = ident.to_string();
+                check_name(&name).map_err(|message| (ident.span(), message))?;
+
+                name
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '_' => "_".to_string(),
+            _ => return Err((token.span(), "expected a token name".to_string())),
+        };
+
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+            Some(other) => return Err((other.span(), "expected '='".to_string())),
+            None => return Err((token.span(), "expected '=' after the name".to_string())),
+        }
+
+        let literal = match tokens.next() {
+            Some(TokenTree::Literal(literal)) => literal,
+            Some(other) => {
+                return Err((other.span(), "expected a string literal".to_string()));
+            }
+            None => return Err((token.span(), "expected a pattern".to_string())),
+        };
+
+        let pattern = match string_value(&literal.to_string()) {
+            Some(pattern) => pattern,
+            None => return Err((literal.span(), "expected a string literal".to_string())),
+        };
+
+        rules.push(MacroRule {
+            name,
+            pattern,
+            span: literal.span(),
+        });
+
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ';' => {}
+            Some(other) => return Err((other.span(), "expected ';'".to_string())),
+            None => {}
+        }
+    }
+
+    if rules.is_empty() {
+        return Err((Span::call_site(), "expected at least one rule".to_string()));
+    }
+
+    Ok(rules)
+}
+
+/// Rejects token names that cannot name a variant of the `Token` enum. `_`
+/// names a skip rule, so it is let through.
+fn check_name(name: &str) -> Result<(), String> {
+    if name != "_" && is_keyword(name) {
+        return Err(SpecErrorKind::KeywordTokenName(name.to_string()).to_string());
+    }
+
+    Ok(())
+}
+
+/// The value of the string literal `literal`, as written in Rust source, or
+/// `None` if it is not a string literal.
+fn string_value(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let inner = raw[hashes..].strip_suffix(&"#".repeat(hashes))?;
+
+        return Some(inner.strip_prefix('"')?.strip_suffix('"')?.to_string());
+    }
+
+    let inner = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::new();
+    let mut chars = inner.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            '0' => value.push('\0'),
+            '\\' => value.push('\\'),
+            '\'' => value.push('\''),
+            '"' => value.push('"'),
+            'x' => {
+                let digits: String = (0..2).filter_map(|_| chars.next()).collect();
+                value.push(u8::from_str_radix(&digits, 16).ok()? as char);
+            }
+            'u' => {
+                chars.next().filter(|&c| c == '{')?;
+
+                let digits: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let code = u32::from_str_radix(&digits.replace('_', ""), 16).ok()?;
+
+                value.push(std::char::from_u32(code)?);
+            }
+            // A line continuation skips the line break and the indentation
+            // after it.
+            '\n' => {
+                while chars.peek().map_or(false, |c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    Some(value)
+}
+
+/// The source of the lexer for `rules`, given as token names and patterns,
+/// or the index of the rule whose pattern is wrong and what is wrong with it.
+fn expand(rules: &[(String, String)]) -> Result<String, (usize, String)> {
+    let tokens: Vec<(&str, &str)> = rules
+        .iter()
+        .map(|(name, pattern)| match name.as_str() {
+            "_" => ("", pattern.as_str()),
+            name => (name, pattern.as_str()),
+        })
+        .collect();
+
+    // A span inside a literal needs the unstable `Literal::subspan`, so the
+    // error is reported on the whole literal, with the pattern reprinted and
+    // the offending part underlined.
+    let nfa = LexerNfa::from_rules(&tokens).map_err(|error| {
+        let pattern = tokens[error.rule].1;
+        let diagnostic = Diagnostic::new(pattern, error.error.span(), error.error.to_string())
+            .with_path("pattern")
+            .to_string();
+
+        // compile_error! adds its own "error: ".
+        let message = diagnostic.strip_prefix("error: ").unwrap_or(&diagnostic);
+
+        (error.rule, message.to_string())
+    })?;
+
+    let mut dfa = LexerDfa::from_nfa(&nfa);
+    dfa.minimize();
+
+    let spec = Spec {
+        rules: tokens
+            .iter()
+            .map(|&(name, pattern)| Rule {
+                pattern: pattern.to_string(),
+                action: match name {
+                    "" => Action::Code(String::new()),
+                    name => Action::Token(name.to_string()),
+                },
+                span: SourceSpan::new(0, 0),
+                modes: vec![0],
+            })
+            .collect(),
+        ..Spec::default()
+    };
+
+    Ok(codegen::generate(&spec, &dfa))
+}
+
+/// `compile_error!(message)`, reported at `span`.
+fn compile_error(span: Span, message: &str) -> TokenStream {
+    let mut literal = Literal::string(message);
+    literal.set_span(span);
+
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+
+    let mut group = Group::new(
+        Delimiter::Brace,
+        TokenStream::from(TokenTree::Literal(literal)),
+    );
+    group.set_span(span);
+
+    vec![
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(bang),
+        TokenTree::Group(group),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_values() {
+        assert_eq!(string_value(r#""a-z+""#), Some("a-z+".to_string()));
+        assert_eq!(
+            string_value(r#""\"if\" | \\n\t""#),
+            Some("\"if\" | \\n\t".to_string())
+        );
+        assert_eq!(string_value(r#""\x41\u{3b1}""#), Some("Aα".to_string()));
+        assert_eq!(string_value("\"a\\\n    b\""), Some("ab".to_string()));
+        assert_eq!(
+            string_value(r###"r#" "if" "#"###),
+            Some(r#" "if" "#.to_string())
+        );
+        assert_eq!(string_value(r#"r"a""#), Some("a".to_string()));
+        assert_eq!(string_value("'a'"), None);
+        assert_eq!(string_value("42"), None);
+        assert_eq!(string_value(r#"b"a""#), None);
+    }
+
+    #[test]
+    fn expands_to_a_lexer() {
+        let rules = vec![
+            ("Ident".to_string(), "a-z+".to_string()),
+            ("_".to_string(), r#"" "+"#.to_string()),
+        ];
+        let code = expand(&rules).unwrap();
+
+        assert!(code.contains("pub enum Token {\n    Ident,\n}"));
+        assert!(code.contains("1 => None,"));
+    }
+
+    #[test]
+    fn reports_the_bad_rule() {
+        let rules = vec![
+            ("A".to_string(), "a".to_string()),
+            ("B".to_string(), "a |".to_string()),
+        ];
+        let (rule, message) = expand(&rules).unwrap_err();
+
+        assert_eq!(rule, 1);
+        assert!(message.starts_with("operator is missing"), "{}", message);
+        assert!(message.ends_with("1 | a |\n  |   ^"), "{}", message);
+    }
+
+    #[test]
+    fn rejects_keyword_names() {
+        assert_eq!(check_name("Ident"), Ok(()));
+        assert_eq!(check_name("_"), Ok(()));
+        assert_eq!(
+            check_name("fn"),
+            Err("token name is a Rust keyword: fn".to_string())
+        );
+        assert!(check_name("Self").is_err());
+    }
+}
@@ -0,0 +1,48 @@
+mod calc {
+    rlex_macros::lexer! {
+        Let = r#" "let" "#;
+        Ident = "(a-z)+ (a-z | 0-9 | _)*";
+        Float = r#" 0-9+ "." 0-9+ "#;
+        Int = "0-9+";
+        Eq = "\"=\"";
+        _ = r#" " " | "\n" "#;
+    }
+}
+
+use calc::{LexError, Lexeme, Lexer, Token};
+
+#[test]
+fn lexes() {
+    let lexemes: Vec<Lexeme> = Lexer::new("let x_1 = 2.5\nlet y = 3")
+        .map(Result::unwrap)
+        .collect();
+
+    let tokens: Vec<(Token, &str)> = lexemes
+        .iter()
+        .map(|lexeme| (lexeme.token, lexeme.text))
+        .collect();
+
+    assert_eq!(
+        tokens,
+        vec![
+            (Token::Let, "let"),
+            (Token::Ident, "x_1"),
+            (Token::Eq, "="),
+            (Token::Float, "2.5"),
+            (Token::Let, "let"),
+            (Token::Ident, "y"),
+            (Token::Eq, "="),
+            (Token::Int, "3"),
+        ]
+    );
+    assert_eq!((lexemes[1].start, lexemes[1].end), (4, 7));
+}
+
+#[test]
+fn errors() {
+    let mut lexer = Lexer::new("x # y");
+
+    assert_eq!(lexer.next().unwrap().unwrap().token, Token::Ident);
+    assert_eq!(lexer.next(), Some(Err(LexError { offset: 2, ch: '#' })));
+    assert_eq!(lexer.offset(), 3);
+}
@@ -0,0 +1,233 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::codegen;
+use crate::spec::{Engine, Spec, SpecError};
+
+/// Generates a lexer from a spec in a Cargo build script.
+///
+/// ```no_run
+/// // In build.rs:
+/// rlex::Builder::new()
+///     .spec("src/lexer.rlex")
+///     .out_file("lexer.rs")
+///     .generate()
+///     .unwrap_or_else(|error| panic!("{}", error));
+/// ```
+///
+/// The crate then pulls the lexer in with
+/// `include!(concat!(env!("OUT_DIR"), "/lexer.rs"));`.
+#[derive(Clone, Debug, Default)]
+pub struct Builder {
+    spec: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+    out_file: Option<PathBuf>,
+    engine: Option<Engine>,
+}
+
+/// Why a `Builder` could not generate its lexer.
+#[derive(Debug)]
+pub enum BuildError {
+    /// `spec` was never called.
+    MissingSpec,
+    /// Neither `out_dir` was called nor is `OUT_DIR` set, as it is when
+    /// Cargo runs a build script.
+    MissingOutDir,
+    /// The spec could not be read or the lexer could not be written.
+    Io(PathBuf, io::Error),
+    /// The spec at the path, whose source is kept to render the error.
+    Spec(PathBuf, String, Box<SpecError>),
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Builder::default()
+    }
+
+    /// The path of the spec, relative to the directory the build script runs
+    /// in, which is the root of the package.
+    pub fn spec(mut self, path: impl AsRef<Path>) -> Self {
+        self.spec = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// The directory to write to, instead of `OUT_DIR`.
+    pub fn out_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// The file to write to, relative to the output directory. Defaults to
+    /// the name of the spec with an `.rs` extension.
+    pub fn out_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.out_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Overrides the engine the spec chooses.
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.engine = Some(engine);
+        self
+    }
+
+    /// Generates the lexer and returns the path it was written to.
+    ///
+    /// Tells Cargo to run the build script again when the spec changes. The
+    /// file is only written when its contents change, so that the crate is
+    /// not rebuilt needlessly.
+    pub fn generate(&self) -> Result<PathBuf, BuildError> {
+        let spec_path = self.spec.as_ref().ok_or(BuildError::MissingSpec)?;
+
+        let out_dir = match &self.out_dir {
+            Some(out_dir) => out_dir.clone(),
+            None => env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or(BuildError::MissingOutDir)?,
+        };
+
+        let out_file = match &self.out_file {
+            Some(out_file) => out_file.clone(),
+            None => Path::new(spec_path.file_stem().unwrap_or_default()).with_extension("rs"),
+        };
+
+        println!("cargo:rerun-if-changed={}", spec_path.display());
+
+        let source = fs::read_to_string(spec_path)
+            .map_err(|error| BuildError::Io(spec_path.clone(), error))?;
+
+        let generated = Spec::parse(&source).and_then(|mut spec| {
+            if let Some(engine) = self.engine {
+                spec.engine = engine;
+            }
+
+            let dfa = spec.build_dfa(&source)?;
+
+            Ok(codegen::generate(&spec, &dfa))
+        });
+
+        let generated = generated.map_err(|error| {
+            BuildError::Spec(spec_path.clone(), source.clone(), Box::new(error))
+        })?;
+
+        let out_path = out_dir.join(out_file);
+
+        if fs::read_to_string(&out_path).ok().as_deref() != Some(generated.as_str()) {
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|error| BuildError::Io(parent.to_path_buf(), error))?;
+            }
+
+            fs::write(&out_path, generated)
+                .map_err(|error| BuildError::Io(out_path.clone(), error))?;
+        }
+
+        Ok(out_path)
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::MissingSpec => write!(f, "no spec to generate a lexer from"),
+            BuildError::MissingOutDir => {
+                write!(f, "OUT_DIR is not set, and no output directory was given")
+            }
+            BuildError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            BuildError::Spec(path, source, error) => {
+                let path = path.to_string_lossy();
+
+                write!(f, "{}", error.diagnostic(source).with_path(&path))
+            }
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::Io(_, error) => Some(error),
+            BuildError::Spec(_, _, error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for one test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rlex-builder-{}-{}", std::process::id(), name));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn writes_into_the_out_dir() {
+        let dir = temp_dir("ok");
+        let spec = dir.join("calc.rlex");
+        fs::write(&spec, "%%\n0-9+ => Number\n").unwrap();
+
+        let path = Builder::new()
+            .spec(&spec)
+            .out_dir(dir.join("out"))
+            .generate()
+            .unwrap();
+
+        assert_eq!(path, dir.join("out").join("calc.rs"));
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("static TRANSITIONS"));
+
+        let path = Builder::new()
+            .spec(&spec)
+            .out_dir(dir.join("out"))
+            .out_file("lexers/lexer.rs")
+            .engine(Engine::Match)
+            .generate()
+            .unwrap();
+
+        assert_eq!(path, dir.join("out").join("lexers").join("lexer.rs"));
+        assert!(fs::read_to_string(&path).unwrap().contains("match ch {"));
+    }
+
+    #[test]
+    fn errors() {
+        let dir = temp_dir("errors");
+        let spec = dir.join("bad.rlex");
+        fs::write(&spec, "%%\na | => A\n").unwrap();
+
+        assert!(matches!(
+            Builder::new().out_dir(&dir).generate(),
+            Err(BuildError::MissingSpec)
+        ));
+        assert!(matches!(
+            Builder::new()
+                .spec(dir.join("missing.rlex"))
+                .out_dir(&dir)
+                .generate(),
+            Err(BuildError::Io(_, _))
+        ));
+
+        let error = Builder::new()
+            .spec(&spec)
+            .out_dir(&dir)
+            .generate()
+            .unwrap_err();
+
+        assert!(matches!(error, BuildError::Spec(_, _, _)));
+        assert!(error.to_string().contains(&format!(
+            "error: operator is missing its right operand\n --> {}:2:3",
+            spec.display()
+        )));
+        assert!(!dir.join("bad.rs").exists());
+    }
+}
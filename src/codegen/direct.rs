@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::fsa::Dfa;
+use crate::regex::next_char;
+
+/// A `match` pattern for the chars from `from` to `to`.
+fn char_pattern(from: char, to: char) -> String {
+    if from == to {
+        format!("{:?}", from)
+    } else {
+        format!("{:?}..={:?}", from, to)
+    }
+}
+
+/// A `match` pattern for the sorted `states`, with runs of consecutive states
+/// written as ranges.
+fn states_pattern(states: &[usize]) -> String {
+    let mut runs: Vec<(usize, usize)> = vec![];
+
+    for &state in states {
+        match runs.last_mut() {
+            Some((_, last)) if *last + 1 == state => *last = state,
+            _ => runs.push((state, state)),
+        }
+    }
+
+    let mut patterns = vec![];
+
+    for (from, to) in runs {
+        match to - from {
+            0 => patterns.push(from.to_string()),
+            1 => patterns.extend(vec![from.to_string(), to.to_string()]),
+            _ => patterns.push(format!("{}..={}", from, to)),
+        }
+    }
+
+    patterns.join(" | ")
+}
+
+/// The arms matching the char of the transitions of `state`, one per target.
+fn transition_arms(dfa: &Dfa, state: usize) -> Vec<(String, usize)> {
+    let mut targets: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+
+    for &(from, to, target) in dfa.transitions(state) {
+        targets
+            .entry(target)
+            .or_default()
+            .push(char_pattern(from, to));
+    }
+
+    targets
+        .into_iter()
+        .map(|(target, patterns)| (patterns.join(" | "), target))
+        .collect()
+}
+
+/// Whether `state` has a transition on every char, so that a `match` on the
+/// char needs no fallback arm.
+fn covers_every_char(dfa: &Dfa, state: usize) -> bool {
+    let mut ranges: Vec<(char, char)> = dfa
+        .transitions(state)
+        .iter()
+        .map(|&(from, to, _)| (from, to))
+        .collect();
+    ranges.sort_unstable();
+
+    let mut next = Some('\0');
+
+    for (from, to) in ranges {
+        if next != Some(from) {
+            return false;
+        }
+
+        next = next_char(to);
+    }
+
+    next.is_none()
+}
+
+/// The arms matching the accepting states, one per rule.
+fn accept_arms(dfa: &Dfa) -> Vec<(String, usize)> {
+    let mut rules: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+    for state in 0..dfa.state_count() {
+        if let Some(rule) = dfa.token_of(state) {
+            rules.entry(rule).or_default().push(state);
+        }
+    }
+
+    rules
+        .into_iter()
+        .map(|(rule, states)| (states_pattern(&states), rule))
+        .collect()
+}
+
+/// Writes `longest_match`, which starts from the state in `starts` of the
+/// current mode if the lexer has modes, named by `modes`, or in `line_starts`
+/// at the start of a line, unless that is empty.
+pub(super) fn write_engine(
+    out: &mut String,
+    dfa: &Dfa,
+    modes: &[String],
+    starts: &[usize],
+    line_starts: &[usize],
+) {
+    out.push_str("    /// The length and rule of the longest non-empty match at the offset.\n");
+    out.push_str("    fn longest_match(&self) -> Option<(usize, usize)> {\n");
+
+    let accept_arms = accept_arms(dfa);
+
+    if accept_arms.is_empty() {
+        out.push_str("        None\n    }\n\n");
+        return;
+    }
+
+    match (modes.is_empty(), line_starts.is_empty()) {
+        (true, true) => writeln!(out, "        let mut state = {};", starts[0]).unwrap(),
+        (true, false) => writeln!(
+            out,
+            "        let mut state = if self.at_line_start() {{ {} }} else {{ {} }};",
+            line_starts[0], starts[0]
+        )
+        .unwrap(),
+        (false, true) => {
+            out.push_str("        let mut state = match self.mode() {\n");
+
+            for (mode, start) in modes.iter().zip(starts) {
+                writeln!(out, "            Mode::{} => {},", mode, start).unwrap();
+            }
+
+            out.push_str("        };\n");
+        }
+        (false, false) => {
+            out.push_str("        let mut state = match (self.mode(), self.at_line_start()) {\n");
+
+            for (mode, (start, line_start)) in modes.iter().zip(starts.iter().zip(line_starts)) {
+                if start == line_start {
+                    writeln!(out, "            (Mode::{}, _) => {},", mode, start).unwrap();
+                } else {
+                    writeln!(out, "            (Mode::{}, false) => {},", mode, start).unwrap();
+                    writeln!(out, "            (Mode::{}, true) => {},", mode, line_start).unwrap();
+                }
+            }
+
+            out.push_str("        };\n");
+        }
+    }
+
+    out.push_str("        let mut last_accept = None;\n\n");
+    out.push_str("        for (index, ch) in self.input[self.offset..].char_indices() {\n");
+    out.push_str("            state = match state {\n");
+
+    let mut stuck = vec![];
+
+    for state in 0..dfa.state_count() {
+        let arms = transition_arms(dfa, state);
+
+        if arms.is_empty() {
+            stuck.push(state);
+            continue;
+        }
+
+        writeln!(out, "                {} => match ch {{", state).unwrap();
+
+        for (pattern, target) in arms {
+            writeln!(out, "                    {} => {},", pattern, target).unwrap();
+        }
+
+        if !covers_every_char(dfa, state) {
+            out.push_str("                    _ => break,\n");
+        }
+
+        out.push_str("                },\n");
+    }
+
+    if !stuck.is_empty() {
+        writeln!(out, "                {} => break,", states_pattern(&stuck)).unwrap();
+    }
+
+    out.push_str("                _ => unreachable!(),\n");
+    out.push_str("            };\n\n");
+    out.push_str("            let rule = match state {\n");
+
+    for (pattern, rule) in accept_arms {
+        writeln!(out, "                {} => {},", pattern, rule).unwrap();
+    }
+
+    out.push_str("                _ => continue,\n");
+    out.push_str("            };\n\n");
+    out.push_str("            last_accept = Some((index + ch.len_utf8(), rule));\n");
+    out.push_str("        }\n\n");
+    out.push_str("        last_accept\n");
+    out.push_str("    }\n\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fsa::{LexerDfa, LexerNfa};
+
+    #[test]
+    fn char_patterns() {
+        assert_eq!(char_pattern('a', 'a'), "'a'");
+        assert_eq!(char_pattern('a', 'z'), "'a'..='z'");
+        assert_eq!(char_pattern('\n', '\u{10FFFF}'), "'\\n'..='\\u{10ffff}'");
+    }
+
+    #[test]
+    fn states_patterns() {
+        assert_eq!(states_pattern(&[3]), "3");
+        assert_eq!(states_pattern(&[1, 2, 5]), "1 | 2 | 5");
+        assert_eq!(
+            states_pattern(&[0, 1, 2, 3, 7, 9, 10, 11]),
+            "0..=3 | 7 | 9..=11"
+        );
+    }
+
+    #[test]
+    fn every_char_covered() {
+        let nfa = LexerNfa::from_rules(&[("A", "[^a]"), ("B", "a"), ("C", "[^\\n]")]).unwrap();
+        let dfa = LexerDfa::from_nfa(&nfa);
+        let dfa = dfa.dfa();
+
+        assert!(covers_every_char(dfa, dfa.sid()));
+
+        let nfa = LexerNfa::from_rules(&[("C", "[^\\n]")]).unwrap();
+        let dfa = LexerDfa::from_nfa(&nfa);
+
+        assert!(!covers_every_char(dfa.dfa(), dfa.sid()));
+    }
+}
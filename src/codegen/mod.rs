@@ -0,0 +1,597 @@
+//! Rust source code for the lexer of a spec.
+//!
+//! The generated module does not depend on rlex. It holds a `Token` enum with
+//! a variant per token name of the rules, a `Lexeme` and a `LexError` type, and
+//! a `Lexer<'a>` iterating over the lexemes of an input, followed by the user
+//! code of the spec. The engine of the spec decides whether the lexer runs its
+//! automaton from tables or from `match` statements; both lex alike.
+//!
+//! A spec declaring modes also gets a `Mode` enum with a variant per mode, in
+//! camel case, and the lexer gets methods for actions to switch modes with.
+//! The lexer of a spec with `<<EOF>>` rules returns a last, empty lexeme when
+//! the input runs out.
+
+mod direct;
+mod table;
+
+use std::fmt::Write;
+
+use crate::fsa::{LexerDfa, TrailingContext};
+use crate::spec::{is_keyword, Action, Engine, Spec, SpecError};
+
+/// Generates the lexer module of `spec`, whose rules compile to `dfa`.
+pub fn generate(spec: &Spec, dfa: &LexerDfa) -> String {
+    let mut out = String::new();
+
+    write_types(&mut out, spec);
+
+    let starts = dfa.starts();
+    let line_starts = line_starts(dfa);
+
+    match spec.engine {
+        Engine::Table => {
+            table::write_tables(&mut out, dfa.dfa(), starts, line_starts, spec.rules.len());
+            write_lexer(&mut out, spec, dfa, |out| {
+                table::write_engine(out, starts.len(), !line_starts.is_empty())
+            });
+        }
+        Engine::Match => write_lexer(&mut out, spec, dfa, |out| {
+            direct::write_engine(out, dfa.dfa(), &mode_variants(spec), starts, line_starts)
+        }),
+    }
+
+    write_user_code(&mut out, spec);
+
+    out
+}
+
+/// Parses the spec in `source` and generates its lexer module.
+pub fn compile(source: &str) -> Result<String, SpecError> {
+    let spec = Spec::parse(source)?;
+    let dfa = spec.build_dfa(source)?;
+
+    Ok(generate(&spec, &dfa))
+}
+
+/// The distinct token names of the rules, in the order they first appear.
+fn token_names(spec: &Spec) -> Vec<&str> {
+    let mut names = vec![];
+
+    for rule in &spec.rules {
+        if let Action::Token(name) = &rule.action {
+            if !names.contains(&name.as_str()) {
+                names.push(name.as_str());
+            }
+        }
+    }
+
+    names
+}
+
+/// The variant names of the `Mode` enum, or none if the spec only has the
+/// initial mode.
+fn mode_variants(spec: &Spec) -> Vec<String> {
+    if spec.modes.len() <= 1 {
+        return vec![];
+    }
+
+    let mut variants: Vec<String> = vec![];
+
+    for (index, mode) in spec.modes.iter().enumerate() {
+        let mut variant: String = mode
+            .split('_')
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                let mut chars = word.chars();
+                let first = chars.next().unwrap();
+
+                first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect::<String>()
+            })
+            .collect();
+
+        // Names such as `_`, `self` or differing only in case still need
+        // distinct variants.
+        if variant.is_empty() || is_keyword(&variant) || variants.contains(&variant) {
+            variant = format!("Mode{}", index);
+        }
+
+        variants.push(variant);
+    }
+
+    variants
+}
+
+/// The start states of the modes at the start of a line, or none if they are
+/// the usual ones.
+fn line_starts(dfa: &LexerDfa) -> &[usize] {
+    if dfa.line_starts() == dfa.starts() {
+        &[]
+    } else {
+        dfa.line_starts()
+    }
+}
+
+/// The smallest unsigned type that holds every value up to `max`.
+fn int_type(max: usize) -> &'static str {
+    if max <= u8::MAX as usize {
+        "u8"
+    } else if max <= u16::MAX as usize {
+        "u16"
+    } else {
+        "u32"
+    }
+}
+
+/// Writes `values` as the elements of an array literal, a few on each line.
+fn write_array<T: std::fmt::Display>(out: &mut String, values: &[T]) {
+    for chunk in values.chunks(16) {
+        let line: Vec<String> = chunk.iter().map(|value| value.to_string()).collect();
+
+        writeln!(out, "    {},", line.join(", ")).unwrap();
+    }
+}
+
+fn write_types(out: &mut String, spec: &Spec) {
+    out.push_str("// Generated by rlex. Do not edit.\n\n");
+
+    out.push_str("#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]\n");
+    out.push_str("pub enum Token {\n");
+
+    for name in token_names(spec) {
+        writeln!(out, "    {},", name).unwrap();
+    }
+
+    out.push_str("}\n\n");
+
+    out.push_str(
+        "\
+/// A token with the text it was matched from and the byte offsets of the
+/// text in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lexeme<'a> {
+    pub token: Token,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A char at which no rule matches, with its byte offset in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub offset: usize,
+    pub ch: char,
+}
+
+",
+    );
+
+    let variants = mode_variants(spec);
+
+    if !variants.is_empty() {
+        out.push_str("/// The modes of the lexer, which decide the rules it matches.\n");
+        out.push_str("#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]\n");
+        out.push_str("pub enum Mode {\n");
+
+        for variant in variants {
+            writeln!(out, "    {},", variant).unwrap();
+        }
+
+        out.push_str("}\n\n");
+    }
+}
+
+/// Writes the `Lexer` type. `write_engine` writes the `longest_match`
+/// method, and anything it needs, into the `impl` block of the lexer.
+fn write_lexer<F>(out: &mut String, spec: &Spec, dfa: &LexerDfa, write_engine: F)
+where
+    F: Fn(&mut String),
+{
+    let variants = mode_variants(spec);
+    let end_rules: Vec<Option<usize>> = (0..dfa.starts().len())
+        .map(|mode| dfa.end_rule(mode))
+        .collect();
+    let ends = end_rules.iter().any(Option::is_some);
+
+    // The fields besides the input and offset, with their initial values.
+    let mut fields = vec![];
+
+    if !variants.is_empty() {
+        fields.push((
+            "modes: Vec<Mode>".to_string(),
+            format!("modes: vec![Mode::{}]", variants[0]),
+        ));
+    }
+
+    if ends {
+        fields.push(("ended: bool".to_string(), "ended: false".to_string()));
+    }
+
+    out.push_str("pub struct Lexer<'a> {\n    input: &'a str,\n    offset: usize,\n");
+
+    for (field, _) in &fields {
+        writeln!(out, "    {},", field).unwrap();
+    }
+
+    out.push_str("}\n\nimpl<'a> Lexer<'a> {\n    pub fn new(input: &'a str) -> Self {\n");
+
+    if fields.is_empty() {
+        out.push_str("        Lexer { input, offset: 0 }\n");
+    } else {
+        out.push_str("        Lexer {\n            input,\n            offset: 0,\n");
+
+        for (_, value) in &fields {
+            writeln!(out, "            {},", value).unwrap();
+        }
+
+        out.push_str("        }\n");
+    }
+
+    out.push_str("    }\n");
+
+    if !variants.is_empty() {
+        out.push_str(
+            "
+    /// The mode on top of the mode stack.
+    pub fn mode(&self) -> Mode {
+        *self.modes.last().unwrap()
+    }
+
+    /// Switches to `mode` until it is popped.
+    pub fn push_mode(&mut self, mode: Mode) {
+        self.modes.push(mode);
+    }
+
+    /// Pops the mode on top of the stack and returns it, unless it is the
+    /// last one.
+    pub fn pop_mode(&mut self) -> Option<Mode> {
+        if self.modes.len() > 1 {
+            self.modes.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the mode on top of the stack.
+    pub fn set_mode(&mut self, mode: Mode) {
+        *self.modes.last_mut().unwrap() = mode;
+    }
+",
+        );
+    }
+
+    out.push_str(
+        "
+    /// The byte offset at which the next lexeme starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+",
+    );
+
+    if !line_starts(dfa).is_empty() {
+        out.push_str(
+            "    /// Whether the next lexeme starts a line.
+    fn at_line_start(&self) -> bool {
+        self.offset == 0 || self.input.as_bytes()[self.offset - 1] == b'\\n'
+    }
+
+",
+        );
+    }
+
+    write_engine(out);
+
+    let contexts = write_token_len(out, spec, dfa);
+
+    if ends {
+        write_end_of_input(out, &variants, &end_rules);
+    }
+
+    write_action(out, spec);
+
+    out.push_str(
+        "\
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Lexeme<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.offset;
+",
+    );
+
+    if ends {
+        out.push_str(
+            "            let ch = match self.input[start..].chars().next() {
+                Some(ch) => ch,
+                None => return self.end_of_input(),
+            };
+",
+        );
+    } else {
+        out.push_str("            let ch = self.input[start..].chars().next()?;\n");
+    }
+
+    out.push_str(
+        "
+            match self.longest_match() {
+                Some((length, rule)) => {
+",
+    );
+
+    if contexts {
+        out.push_str("                    self.offset += self.token_len(length, rule);\n");
+    } else {
+        out.push_str("                    self.offset += length;\n");
+    }
+
+    out.push_str(
+        "
+                    let text = &self.input[start..self.offset];
+
+                    if let Some(token) = self.action(rule, text) {
+                        let end = self.offset;
+
+                        return Some(Ok(Lexeme { token, text, start, end }));
+                    }
+                }
+                None => {
+                    self.offset += ch.len_utf8();
+
+                    return Some(Err(LexError { offset: start, ch }));
+                }
+            }
+        }
+    }
+}
+",
+    );
+}
+
+/// Writes the `token_len` method, which drops the trailing context from a
+/// match, and returns whether any rule needs it.
+fn write_token_len(out: &mut String, spec: &Spec, dfa: &LexerDfa) -> bool {
+    let contexts: Vec<(usize, TrailingContext)> = (0..spec.rules.len())
+        .filter_map(|rule| dfa.context_of(rule).map(|context| (rule, context)))
+        .collect();
+
+    if contexts.is_empty() {
+        return false;
+    }
+
+    out.push_str(
+        "    /// The length of the token in the `length` bytes at the offset that
+    /// `rule` matched, without its trailing context.
+    fn token_len(&self, length: usize, rule: usize) -> usize {
+        let text = &self.input[self.offset..self.offset + length];
+
+        match rule {
+",
+    );
+
+    for (rule, context) in contexts {
+        match context {
+            TrailingContext::Head(count) => writeln!(
+                out,
+                "            {} => text.char_indices().nth({}).map_or(length, |(index, _)| index),",
+                rule, count
+            )
+            .unwrap(),
+            TrailingContext::Tail(0) => {}
+            TrailingContext::Tail(count) => writeln!(
+                out,
+                "            {} => text.char_indices().rev().nth({}).map_or(0, |(index, _)| index),",
+                rule,
+                count - 1
+            )
+            .unwrap(),
+        }
+    }
+
+    out.push_str(
+        "            _ => length,
+        }
+    }
+
+",
+    );
+
+    true
+}
+
+/// Writes the `end_of_input` method, which runs the `<<EOF>>` rule of the
+/// current mode, given by `end_rules`, the first time the input runs out.
+fn write_end_of_input(out: &mut String, variants: &[String], end_rules: &[Option<usize>]) {
+    out.push_str(
+        "    /// The lexeme of the end of the input, the first time it runs out.
+    fn end_of_input(&mut self) -> Option<Result<Lexeme<'a>, LexError>> {
+        if self.ended {
+            return None;
+        }
+
+        self.ended = true;
+
+",
+    );
+
+    match end_rules {
+        [Some(rule), rest @ ..] if rest.iter().all(|end| *end == Some(*rule)) => {
+            writeln!(out, "        let rule = {};", rule).unwrap()
+        }
+        _ => {
+            out.push_str("        let rule = match self.mode() {\n");
+
+            for (variant, end) in variants.iter().zip(end_rules) {
+                if let Some(rule) = end {
+                    writeln!(out, "            Mode::{} => {},", variant, rule).unwrap();
+                }
+            }
+
+            if end_rules.contains(&None) {
+                out.push_str("            _ => return None,\n");
+            }
+
+            out.push_str("        };\n");
+        }
+    }
+
+    out.push_str(
+        "
+        let token = self.action(rule, \"\")?;
+        let start = self.offset;
+
+        Some(Ok(Lexeme { token, text: \"\", start, end: start }))
+    }
+
+",
+    );
+}
+
+fn write_action(out: &mut String, spec: &Spec) {
+    out.push_str(
+        "    /// The token to return for `text`, matched by `rule`, if any.
+    #[allow(unused_variables)]
+    fn action(&mut self, rule: usize, text: &'a str) -> Option<Token> {
+        match rule {
+",
+    );
+
+    for (index, rule) in spec.rules.iter().enumerate() {
+        match &rule.action {
+            Action::Token(name) => {
+                writeln!(out, "            {} => Some(Token::{}),", index, name).unwrap()
+            }
+            Action::Code(code) if code.is_empty() => {
+                writeln!(out, "            {} => None,", index).unwrap()
+            }
+            Action::Code(code) => {
+                writeln!(out, "            {} => {{", index).unwrap();
+
+                for line in dedent(code) {
+                    if line.is_empty() {
+                        out.push('\n');
+                    } else {
+                        writeln!(out, "                {}", line).unwrap();
+                    }
+                }
+
+                out.push_str("            }\n");
+            }
+        }
+    }
+
+    out.push_str(
+        "            _ => unreachable!(),
+        }
+    }
+",
+    );
+}
+
+/// The lines of `code`, which starts right after a brace, without the
+/// indentation its later lines share.
+fn dedent(code: &str) -> Vec<&str> {
+    let mut lines = code.lines();
+    let first = lines.next().unwrap_or_default();
+    let rest: Vec<&str> = lines.collect();
+
+    let indent = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    std::iter::once(first)
+        .chain(rest.iter().map(|line| {
+            if line.trim().is_empty() {
+                ""
+            } else {
+                &line[indent..]
+            }
+        }))
+        .collect()
+}
+
+fn write_user_code(out: &mut String, spec: &Spec) {
+    if let Some(code) = &spec.user_code {
+        let code = code.trim();
+
+        if !code.is_empty() {
+            writeln!(out, "\n{}", code).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_names_are_distinct() {
+        let spec = Spec::parse("%%\na => A\nb => { None }\nc => A\nd => D\n").unwrap();
+
+        assert_eq!(token_names(&spec), vec!["A", "D"]);
+    }
+
+    #[test]
+    fn int_types() {
+        assert_eq!(int_type(255), "u8");
+        assert_eq!(int_type(256), "u16");
+        assert_eq!(int_type(70_000), "u32");
+    }
+
+    #[test]
+    fn dedent_keeps_relative_indentation() {
+        let code = "if x {\n        y\n\n    }";
+
+        assert_eq!(dedent(code), vec!["if x {", "    y", "", "}"]);
+    }
+
+    #[test]
+    fn actions() {
+        let spec = Spec::parse("%%\na => A\n\" \" => {}\nb => {\n    Some(Token::A)\n}\n").unwrap();
+        let mut out = String::new();
+
+        write_action(&mut out, &spec);
+
+        assert!(out.contains("            0 => Some(Token::A),\n"));
+        assert!(out.contains("            1 => None,\n"));
+        assert!(out.contains("            2 => {\n                Some(Token::A)\n            }\n"));
+    }
+
+    #[test]
+    fn engine_of_the_spec() {
+        let table = compile("%%\na => A\n").unwrap();
+        let direct = compile("%option match\n%%\na => A\n").unwrap();
+
+        assert!(table.contains("static TRANSITIONS"));
+        assert!(!direct.contains("static TRANSITIONS"));
+        assert!(direct.contains("match ch {"));
+    }
+
+    #[test]
+    fn mode_variants_are_camel_case() {
+        let spec = Spec::parse("%x IN_STRING comment\n%s _ Comment self\n%%\na => A\n").unwrap();
+
+        assert_eq!(
+            mode_variants(&spec),
+            vec!["Initial", "InString", "Comment", "Mode3", "Mode4", "Mode5"]
+        );
+        assert!(mode_variants(&Spec::parse("%%\na => A\n").unwrap()).is_empty());
+        assert!(!compile("%%\na => A\n").unwrap().contains("enum Mode"));
+    }
+
+    #[test]
+    fn compile_reports_spec_errors() {
+        let error = compile("%%\na{2000} => A\n").unwrap_err();
+
+        assert_eq!((error.line, error.column), (2, 2));
+    }
+}
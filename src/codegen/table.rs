@@ -0,0 +1,297 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+use super::{int_type, write_array};
+use crate::fsa::Dfa;
+use crate::regex::{next_char, prev_char};
+
+/// Sorted, inclusive ranges of chars with the class of each.
+type ClassRanges = Vec<(char, char, usize)>;
+
+/// Splits the chars into ranges that every state of `dfa` moves alike on, and
+/// gives ranges on which all states move to the same states the same class.
+///
+/// Returns the sorted ranges with their classes, and for every class the state
+/// each state moves to, as one more than its id, or 0 for no transition.
+/// Class 0 is the class of chars without any transition.
+pub(super) fn classes_of(dfa: &Dfa) -> (ClassRanges, Vec<Vec<usize>>) {
+    let mut bounds = BTreeSet::new();
+    bounds.insert('\0');
+
+    for state in 0..dfa.state_count() {
+        for &(from, to, _) in dfa.transitions(state) {
+            bounds.insert(from);
+            bounds.extend(next_char(to));
+        }
+    }
+
+    let bounds: Vec<char> = bounds.into_iter().collect();
+
+    let mut columns = vec![vec![0; dfa.state_count()]];
+    let mut ids = HashMap::new();
+    let mut ranges: ClassRanges = vec![];
+
+    ids.insert(columns[0].clone(), 0);
+
+    for (index, &from) in bounds.iter().enumerate() {
+        let to = match bounds.get(index + 1) {
+            Some(&next) => prev_char(next).unwrap(),
+            None => char::MAX,
+        };
+
+        let column: Vec<usize> = (0..dfa.state_count())
+            .map(|state| dfa.next(state, from).map_or(0, |target| target + 1))
+            .collect();
+
+        let class = match ids.get(&column) {
+            Some(&class) => class,
+            None => {
+                ids.insert(column.clone(), columns.len());
+                columns.push(column);
+
+                columns.len() - 1
+            }
+        };
+
+        match ranges.last_mut() {
+            Some((_, last_to, last_class)) if *last_class == class => *last_to = to,
+            _ => ranges.push((from, to, class)),
+        }
+    }
+
+    (ranges, columns)
+}
+
+/// Writes the tables of `dfa`, which the lexer starts from the state in
+/// `starts` of its mode, or in `line_starts` at the start of a line, unless
+/// that is empty.
+pub(super) fn write_tables(
+    out: &mut String,
+    dfa: &Dfa,
+    starts: &[usize],
+    line_starts: &[usize],
+    rule_count: usize,
+) {
+    let (ranges, columns) = classes_of(dfa);
+
+    let state_type = int_type(dfa.state_count());
+    let class_type = int_type(columns.len() - 1);
+    let rule_type = int_type(rule_count);
+
+    let class_of = |c: u32| {
+        ranges
+            .iter()
+            .find(|&&(from, to, _)| from as u32 <= c && c <= to as u32)
+            .map_or(0, |&(_, _, class)| class)
+    };
+
+    let ascii: Vec<usize> = (0..128).map(class_of).collect();
+
+    let wide: Vec<String> = ranges
+        .iter()
+        .filter(|&&(_, to, class)| class != 0 && to as u32 >= 128)
+        .map(|&(from, to, class)| {
+            format!(
+                "({:#x}, {:#x}, {})",
+                (from as u32).max(128),
+                to as u32,
+                class
+            )
+        })
+        .collect();
+
+    // Row 0 is the dead state, and the state with id `n` is on row `n + 1`.
+    let mut transitions = vec![0; columns.len()];
+
+    for state in 0..dfa.state_count() {
+        transitions.extend(columns.iter().map(|column| column[state]));
+    }
+
+    let mut accept = vec![0];
+
+    accept
+        .extend((0..dfa.state_count()).map(|state| dfa.token_of(state).map_or(0, |rule| rule + 1)));
+
+    if let ([start], []) = (starts, line_starts) {
+        writeln!(out, "const START: usize = {};", start + 1).unwrap();
+    } else {
+        let starts: Vec<usize> = starts
+            .iter()
+            .chain(line_starts)
+            .map(|start| start + 1)
+            .collect();
+
+        if line_starts.is_empty() {
+            out.push_str("// The start state of every mode.\n");
+        } else {
+            out.push_str("// The start state of every mode, then at the start of a line.\n");
+        }
+
+        writeln!(out, "static STARTS: [usize; {}] = [", starts.len()).unwrap();
+        write_array(out, &starts);
+        out.push_str("];\n\n");
+    }
+
+    writeln!(out, "const CLASSES: usize = {};", columns.len()).unwrap();
+    out.push('\n');
+
+    out.push_str("// The class of every ASCII char.\n");
+    writeln!(out, "static ASCII_CLASSES: [{}; 128] = [", class_type).unwrap();
+    write_array(out, &ascii);
+    out.push_str("];\n\n");
+
+    out.push_str("// The classes of the other chars, as sorted ranges of code points.\n");
+    writeln!(
+        out,
+        "static CLASS_RANGES: [(u32, u32, {}); {}] = [",
+        class_type,
+        wide.len()
+    )
+    .unwrap();
+
+    for range in &wide {
+        writeln!(out, "    {},", range).unwrap();
+    }
+
+    out.push_str("];\n\n");
+
+    out.push_str("// The next state of every state on every class, one row per state.\n");
+    out.push_str("// State 0 has no transitions, and is where missing transitions lead.\n");
+    writeln!(
+        out,
+        "static TRANSITIONS: [{}; {}] = [",
+        state_type,
+        transitions.len()
+    )
+    .unwrap();
+
+    for row in transitions.chunks(columns.len()) {
+        write_array(out, row);
+    }
+
+    out.push_str("];\n\n");
+
+    out.push_str("// One more than the rule every state accepts, or 0.\n");
+    writeln!(out, "static ACCEPT: [{}; {}] = [", rule_type, accept.len()).unwrap();
+    write_array(out, &accept);
+    out.push_str("];\n\n");
+}
+
+/// Writes `longest_match`, which starts from the state of the current mode
+/// if the lexer has `mode_count` modes, and at the start of a line if
+/// `anchored`.
+pub(super) fn write_engine(out: &mut String, mode_count: usize, anchored: bool) {
+    out.push_str(
+        "    fn class_of(ch: char) -> usize {
+        let code = ch as u32;
+
+        if code < 128 {
+            return ASCII_CLASSES[code as usize] as usize;
+        }
+
+        let found = CLASS_RANGES.binary_search_by(|&(from, to, _)| {
+            if to < code {
+                std::cmp::Ordering::Less
+            } else if from > code {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        });
+
+        match found {
+            Ok(index) => CLASS_RANGES[index].2 as usize,
+            Err(_) => 0,
+        }
+    }
+
+    /// The length and rule of the longest non-empty match at the offset.
+    fn longest_match(&self) -> Option<(usize, usize)> {
+",
+    );
+
+    match (mode_count > 1, anchored) {
+        (true, true) => writeln!(
+            out,
+            "        let mut state = STARTS[self.mode() as usize + {} * self.at_line_start() as usize];",
+            mode_count
+        )
+        .unwrap(),
+        (true, false) => out.push_str("        let mut state = STARTS[self.mode() as usize];\n"),
+        (false, true) => {
+            out.push_str("        let mut state = STARTS[self.at_line_start() as usize];\n")
+        }
+        (false, false) => out.push_str("        let mut state = START;\n"),
+    }
+
+    out.push_str(
+        "        let mut last_accept = None;
+
+        for (index, ch) in self.input[self.offset..].char_indices() {
+            state = TRANSITIONS[state * CLASSES + Lexer::class_of(ch)] as usize;
+
+            if state == 0 {
+                break;
+            }
+
+            if ACCEPT[state] != 0 {
+                last_accept = Some((index + ch.len_utf8(), ACCEPT[state] as usize - 1));
+            }
+        }
+
+        last_accept
+    }
+
+",
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fsa::{LexerDfa, LexerNfa};
+
+    fn dfa_of(rules: &[(&str, &str)]) -> Dfa {
+        let mut dfa = LexerDfa::from_nfa(&LexerNfa::from_rules(rules).unwrap());
+        dfa.minimize();
+
+        dfa.dfa().clone()
+    }
+
+    #[test]
+    fn chars_alike_share_a_class() {
+        let dfa = dfa_of(&[("X", "x"), ("Ident", "a-z+"), ("Number", "0-9+")]);
+        let (ranges, columns) = classes_of(&dfa);
+
+        let class_of = |c: char| {
+            ranges
+                .iter()
+                .find(|&&(from, to, _)| from <= c && c <= to)
+                .unwrap()
+                .2
+        };
+
+        assert_eq!(class_of('a'), class_of('w'));
+        assert_eq!(class_of('0'), class_of('9'));
+        assert_ne!(class_of('a'), class_of('x'));
+        assert_ne!(class_of('a'), class_of('0'));
+        assert_eq!(class_of('!'), 0);
+        assert_eq!(class_of('\u{10FFFF}'), 0);
+        assert_eq!(columns.len(), 4);
+        assert!(columns[0].iter().all(|&target| target == 0));
+    }
+
+    #[test]
+    fn ranges_cover_every_char() {
+        let dfa = dfa_of(&[("Greek", "α-ω+")]);
+        let (ranges, _) = classes_of(&dfa);
+
+        assert_eq!(ranges.first().unwrap().0, '\0');
+        assert_eq!(ranges.last().unwrap().1, char::MAX);
+
+        for pair in ranges.windows(2) {
+            assert_eq!(next_char(pair[0].1), Some(pair[1].0));
+            assert_ne!(pair[0].2, pair[1].2);
+        }
+    }
+}
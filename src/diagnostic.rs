@@ -0,0 +1,201 @@
+use std::fmt;
+
+use crate::regex::Span;
+
+/// A human readable report of a problem in some source text.
+///
+/// Rendering reprints the source line containing the problem and underlines
+/// the offending span:
+///
+/// ```text
+/// error: unsupported character: #
+///  --> 1:5
+///   |
+/// 1 | a . # . b
+///   |     ^
+/// ```
+pub struct Diagnostic<'a> {
+    source: &'a str,
+    span: Span,
+    message: String,
+    path: Option<&'a str>,
+}
+
+impl<'a> Diagnostic<'a> {
+    pub fn new(source: &'a str, span: Span, message: impl Into<String>) -> Self {
+        Diagnostic {
+            source,
+            span,
+            message: message.into(),
+            path: None,
+        }
+    }
+
+    /// Names the file the source was read from in the location line.
+    pub fn with_path(mut self, path: &'a str) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// One-based line and column (counted in chars) of the start of the span.
+    pub fn location(&self) -> (usize, usize) {
+        let (line_index, line_start, _) = self.line();
+
+        (
+            line_index + 1,
+            column_of(self.source, line_start, self.span.start) + 1,
+        )
+    }
+
+    /// Index, start and end byte offsets of the line containing the span,
+    /// without the '\r' of a CRLF line break.
+    fn line(&self) -> (usize, usize, usize) {
+        let start = self.span.start.min(self.source.len());
+
+        let line_start = self.source[..floor_boundary(self.source, start)]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let mut line_end = self.source[line_start..]
+            .find('\n')
+            .map_or(self.source.len(), |index| line_start + index);
+
+        if self.source[line_start..line_end].ends_with('\r') {
+            line_end -= 1;
+        }
+
+        let line_index = self.source[..line_start].matches('\n').count();
+
+        (line_index, line_start, line_end)
+    }
+}
+
+fn floor_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+
+    index
+}
+
+fn column_of(text: &str, line_start: usize, offset: usize) -> usize {
+    text[line_start..floor_boundary(text, offset.max(line_start))]
+        .chars()
+        .count()
+}
+
+impl<'a> fmt::Display for Diagnostic<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (line_index, line_start, line_end) = self.line();
+        let (line, column) = self.location();
+
+        let start = column - 1;
+        let end = column_of(self.source, line_start, self.span.end.min(line_end));
+        let width = end.saturating_sub(start).max(1);
+
+        let number = line.to_string();
+        let gutter = " ".repeat(number.len());
+
+        // Tabs are kept so that the caret lines up however they are shown.
+        let padding: String = self.source[line_start..line_end]
+            .chars()
+            .take(start)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        writeln!(f, "error: {}", self.message)?;
+        match self.path {
+            Some(path) => writeln!(f, "{}--> {}:{}:{}", gutter, path, line_index + 1, column)?,
+            None => writeln!(f, "{}--> {}:{}", gutter, line_index + 1, column)?,
+        }
+
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", number, &self.source[line_start..line_end])?;
+        write!(f, "{} | {}^{}", gutter, padding, "~".repeat(width - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_char_span() {
+        let diagnostic = Diagnostic::new("a . # . b", Span::new(4, 5), "unsupported character: #");
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: unsupported character: #\n --> 1:5\n  |\n1 | a . # . b\n  |     ^"
+        );
+    }
+
+    #[test]
+    fn multi_char_span() {
+        let diagnostic =
+            Diagnostic::new(r#"a . "if"#, Span::new(4, 7), "could not find closing '\"'");
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: could not find closing '\"'\n --> 1:5\n  |\n1 | a . \"if\n  |     ^~~"
+        );
+    }
+
+    #[test]
+    fn span_on_second_line() {
+        let diagnostic = Diagnostic::new("a .\n b | )", Span::new(9, 10), "unbalanced parenthesis");
+
+        assert_eq!(diagnostic.location(), (2, 6));
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: unbalanced parenthesis\n --> 2:6\n  |\n2 |  b | )\n  |      ^"
+        );
+    }
+
+    #[test]
+    fn with_path() {
+        let diagnostic =
+            Diagnostic::new("a |", Span::new(3, 3), "expected operand").with_path("a.rlex");
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: expected operand\n --> a.rlex:1:4\n  |\n1 | a |\n  |    ^"
+        );
+    }
+
+    #[test]
+    fn crlf_line_break_is_not_quoted() {
+        let diagnostic = Diagnostic::new("a\r\nb |\r\nc", Span::new(5, 6), "expected operand");
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: expected operand\n --> 2:3\n  |\n2 | b |\n  |   ^"
+        );
+    }
+
+    #[test]
+    fn caret_padding_keeps_tabs() {
+        let diagnostic = Diagnostic::new("\ta\t#", Span::new(3, 4), "unsupported character: #");
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: unsupported character: #\n --> 1:4\n  |\n1 | \ta\t#\n  | \t \t^"
+        );
+    }
+
+    #[test]
+    fn empty_span_at_end() {
+        let diagnostic = Diagnostic::new("a |", Span::new(3, 3), "expected operand");
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: expected operand\n --> 1:4\n  |\n1 | a |\n  |    ^"
+        );
+    }
+}
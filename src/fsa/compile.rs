@@ -0,0 +1,866 @@
+use super::{LexerNfa, Nfa, RuleError, TrailingContext};
+use crate::regex::{parse, Ast, Definitions, Element, RegexError, Span, Spanned};
+
+/// The default for the largest repetition bound the Nfa builder expands.
+pub const REPEAT_LIMIT: usize = 1000;
+
+struct Compiler {
+    next_id: usize,
+    repeat_limit: usize,
+    // How many copies of the current sub-tree the enclosing repetitions
+    // expand into.
+    copies: usize,
+}
+
+/// The Nfa of a rule, with what it needs from the lexer besides.
+struct RuleNfa {
+    nfa: Nfa,
+    context: Option<TrailingContext>,
+    // Whether the rule only matches at the start of a line.
+    line_start: bool,
+    // Whether the rule is `<<EOF>>`, whose Nfa never matches.
+    end_of_input: bool,
+}
+
+fn line_start_span(ast: &Ast) -> Option<Span> {
+    match ast {
+        Ast::LineStart(span) => Some(*span),
+        _ => None,
+    }
+}
+
+fn line_end_span(ast: &Ast) -> Option<Span> {
+    match ast {
+        Ast::LineEnd(span) => Some(*span),
+        _ => None,
+    }
+}
+
+/// Takes the anchor that `anchor` finds off the start of `ast`, or its end
+/// if `at_end`, and returns the rest with the span of the anchor.
+fn strip_anchor<F>(ast: &Ast, at_end: bool, anchor: F) -> Option<(Ast, Span)>
+where
+    F: Fn(&Ast) -> Option<Span>,
+{
+    if let Some(span) = anchor(ast) {
+        return Some((Ast::Empty, span));
+    }
+
+    let parts = match ast {
+        Ast::Concat(parts) => parts,
+        _ => return None,
+    };
+
+    let (part, rest) = if at_end {
+        parts.split_last()?
+    } else {
+        parts.split_first()?
+    };
+
+    anchor(part).map(|span| (Ast::Concat(rest.to_vec()), span))
+}
+
+impl Compiler {
+    fn new(repeat_limit: usize) -> Self {
+        Compiler {
+            next_id: 0,
+            repeat_limit,
+            copies: 1,
+        }
+    }
+
+    fn alloc(&mut self, count: usize) -> usize {
+        let offset = self.next_id;
+        self.next_id += count;
+
+        offset
+    }
+
+    fn compile_text(&mut self, text: &str) -> Nfa {
+        Nfa::of_text(text, self.alloc(text.chars().count() + 1))
+    }
+
+    fn compile_chain<F>(&mut self, asts: &[Ast], join: F) -> Result<Nfa, RegexError>
+    where
+        F: Fn(&mut Compiler, Nfa, Nfa) -> Nfa,
+    {
+        match asts.split_first() {
+            Some((first, rest)) => {
+                let mut nfa = self.compile(first)?;
+
+                for ast in rest {
+                    let next = self.compile(ast)?;
+
+                    nfa = join(self, nfa, next);
+                }
+
+                Ok(nfa)
+            }
+            None => Ok(Nfa::of_eps(self.alloc(2))),
+        }
+    }
+
+    fn compile_repeat(
+        &mut self,
+        ast: &Ast,
+        min: usize,
+        max: Option<usize>,
+        span: Span,
+    ) -> Result<Nfa, RegexError> {
+        let nfa = match (min, max) {
+            (0, None) => {
+                let nfa = self.compile(ast)?;
+
+                Nfa::of_star(nfa, self.alloc(2))
+            }
+            (1, None) => {
+                let nfa = self.compile(ast)?;
+
+                Nfa::of_plus(nfa, self.alloc(2))
+            }
+            (0, Some(1)) => {
+                let nfa = self.compile(ast)?;
+
+                Nfa::of_question(nfa, self.alloc(2))
+            }
+            _ => {
+                // Every copy becomes a separate sub-automaton, so refuse
+                // bounds that would make the Nfa huge, counting the copies
+                // the enclosing repetitions make of this one.
+                let copies = self.copies.saturating_mul(max.unwrap_or(min));
+
+                if copies > self.repeat_limit {
+                    return Err(RegexError::RepeatTooLarge(self.repeat_limit, span));
+                }
+
+                // Expand into `min` required copies followed by either a
+                // starred copy or `max - min` optional ones.
+                let optional = match max {
+                    Some(max) => vec![(0, Some(1)); max - min],
+                    None => vec![(0, None)],
+                };
+
+                let parts: Vec<Ast> = std::iter::repeat(ast.clone())
+                    .take(min)
+                    .chain(optional.into_iter().map(|(min, max)| Ast::Repeat {
+                        ast: Box::new(ast.clone()),
+                        min,
+                        max,
+                        span,
+                    }))
+                    .collect();
+
+                let outer = std::mem::replace(&mut self.copies, copies);
+                let nfa = self.compile(&Ast::Concat(parts));
+                self.copies = outer;
+
+                nfa?
+            }
+        };
+
+        Ok(nfa)
+    }
+
+    fn compile(&mut self, ast: &Ast) -> Result<Nfa, RegexError> {
+        let nfa = match ast {
+            Ast::Alternation(branches) => self
+                .compile_chain(branches, |compiler, nfa1, nfa2| {
+                    Nfa::of_or(nfa1, nfa2, compiler.alloc(2))
+                })?,
+            Ast::Concat(parts) => {
+                self.compile_chain(parts, |_, nfa1, nfa2| Nfa::of_concat(nfa1, nfa2, 0))?
+            }
+            Ast::Repeat {
+                ast,
+                min,
+                max,
+                span,
+            } => self.compile_repeat(ast, *min, *max, *span)?,
+            Ast::Range(c1, c2) => Nfa::of_cdash(*c1, *c2, self.alloc(2)),
+            Ast::Class(class) => Nfa::of_class(class.clone(), self.alloc(2)),
+            Ast::Literal(text) => self.compile_text(text),
+            Ast::Empty => Nfa::of_eps(self.alloc(2)),
+            Ast::Reference(name, _) => self.compile_text(name),
+            Ast::Trailing { span, .. } => {
+                return Err(RegexError::MisplacedTrailingContext(*span));
+            }
+            Ast::LineStart(span) | Ast::LineEnd(span) | Ast::EndOfInput(span) => {
+                return Err(RegexError::MisplacedAnchor(*span));
+            }
+        };
+
+        Ok(nfa)
+    }
+
+    /// Compiles the pattern of a rule, which may start with `^` and end in
+    /// trailing context or `$`, which is the same as `/ "\n"`, or be
+    /// `<<EOF>>`.
+    fn compile_rule(&mut self, ast: &Ast) -> Result<RuleNfa, RegexError> {
+        if let Ast::EndOfInput(_) = ast {
+            let id = self.alloc(2);
+
+            return Ok(RuleNfa {
+                nfa: Nfa::with_ids(id, id + 1),
+                context: None,
+                line_start: false,
+                end_of_input: true,
+            });
+        }
+
+        let (ast, line_start) = match ast {
+            Ast::Trailing { head, tail, span } => {
+                match strip_anchor(head, false, line_start_span) {
+                    Some((head, _)) => {
+                        let ast = Ast::Trailing {
+                            head: Box::new(head),
+                            tail: tail.clone(),
+                            span: *span,
+                        };
+
+                        (ast, true)
+                    }
+                    None => (ast.clone(), false),
+                }
+            }
+            ast => match strip_anchor(ast, false, line_start_span) {
+                Some((ast, _)) => (ast, true),
+                None => (ast.clone(), false),
+            },
+        };
+
+        let ast = match strip_anchor(&ast, true, line_end_span) {
+            Some((head, span)) => Ast::Trailing {
+                head: Box::new(head),
+                tail: Box::new(Ast::Literal("\n".to_string())),
+                span,
+            },
+            None => ast,
+        };
+
+        let (nfa, context) = self.compile_trailing(&ast)?;
+
+        Ok(RuleNfa {
+            nfa,
+            context,
+            line_start,
+            end_of_input: false,
+        })
+    }
+
+    /// Compiles `ast`, which may be trailing context. The context is matched
+    /// like the rest of the pattern, and the returned `TrailingContext` tells
+    /// how much of the match is the token.
+    fn compile_trailing(
+        &mut self,
+        ast: &Ast,
+    ) -> Result<(Nfa, Option<TrailingContext>), RegexError> {
+        let (head, tail, span) = match ast {
+            Ast::Trailing { head, tail, span } => (head, tail, *span),
+            ast => return Ok((self.compile(ast)?, None)),
+        };
+
+        let context = match (head.fixed_length(), tail.fixed_length()) {
+            (Some(length), _) => TrailingContext::Head(length),
+            (None, Some(length)) => TrailingContext::Tail(length),
+            (None, None) => return Err(RegexError::VariableTrailingContext(span)),
+        };
+
+        let head = self.compile(head)?;
+
+        // A token must not be empty, or the lexer would never move on.
+        if head.closure(vec![head.sid()]).contains(&head.fid()) {
+            return Err(RegexError::EmptyTrailingHead(span));
+        }
+
+        let tail = self.compile(tail)?;
+
+        Ok((Nfa::of_concat(head, tail, 0), Some(context)))
+    }
+}
+
+impl Nfa {
+    /// Builds a single Nfa out of a regex tree.
+    ///
+    /// State ids are allocated internally, starting from zero, so the ids of
+    /// the sub-automata never collide. References are matched as literal
+    /// text. Repetitions expanding their operand into more than `REPEAT_LIMIT`
+    /// copies, counting those of enclosing repetitions, are reported as
+    /// errors.
+    pub fn from_ast(ast: &Ast) -> Result<Self, RegexError> {
+        Nfa::from_ast_with_limit(ast, REPEAT_LIMIT)
+    }
+
+    /// Builds a single Nfa out of a regex tree, reporting repetitions that
+    /// expand into more than `repeat_limit` copies as errors.
+    pub fn from_ast_with_limit(ast: &Ast, repeat_limit: usize) -> Result<Self, RegexError> {
+        Compiler::new(repeat_limit).compile(ast)
+    }
+
+    /// Builds a single Nfa out of a postfix regex, as produced by `to_postfix`.
+    ///
+    /// Malformed postfix input is reported with the span of the element that
+    /// caused the problem.
+    pub fn from_postfix(postfix: &[Spanned<Element>]) -> Result<Self, RegexError> {
+        Nfa::from_ast(&Ast::from_postfix(postfix)?)
+    }
+
+    /// Parses `regex` and builds its Nfa.
+    pub fn from_regex(regex: &str) -> Result<Self, RegexError> {
+        Nfa::from_ast(&parse(regex)?)
+    }
+
+    /// Parses `regex` and builds its Nfa, splicing in the patterns that its
+    /// references are bound to in `definitions`.
+    pub fn from_regex_with(regex: &str, definitions: &Definitions) -> Result<Self, RegexError> {
+        Nfa::from_ast(&definitions.resolve(&parse(regex)?)?)
+    }
+}
+
+impl LexerNfa {
+    /// Parses the pattern of every `(token_name, pattern)` rule and joins
+    /// their Nfas. References are matched as literal text.
+    pub fn from_rules(rules: &[(&str, &str)]) -> Result<Self, RuleError> {
+        LexerNfa::from_rules_with(rules, &Definitions::new())
+    }
+
+    /// Parses the pattern of every `(token_name, pattern)` rule, splicing in
+    /// the patterns bound in `definitions`, and joins their Nfas.
+    pub fn from_rules_with(
+        rules: &[(&str, &str)],
+        definitions: &Definitions,
+    ) -> Result<Self, RuleError> {
+        let modes = vec![vec![0]; rules.len()];
+
+        LexerNfa::from_rules_in_modes(rules, &modes, 1, definitions)
+    }
+
+    /// Like `from_rules_with`, for a lexer with `mode_count` modes. `modes`
+    /// gives the indices of the modes every rule is active in.
+    ///
+    /// Rules starting with `^` are joined to the start states of their modes
+    /// at the start of a line, which only exist if there are such rules. The
+    /// first `<<EOF>>` rule of every mode becomes its end rule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `modes` does not have an entry for every rule, or if it
+    /// names a mode that is not less than `mode_count`.
+    pub fn from_rules_in_modes(
+        rules: &[(&str, &str)],
+        modes: &[Vec<usize>],
+        mode_count: usize,
+        definitions: &Definitions,
+    ) -> Result<Self, RuleError> {
+        assert_eq!(modes.len(), rules.len(), "every rule needs its modes");
+        assert!(
+            modes.iter().flatten().all(|&mode| mode < mode_count),
+            "mode index out of range"
+        );
+
+        // One compiler for all the rules, so their state ids never collide.
+        let mut compiler = Compiler::new(REPEAT_LIMIT);
+        let mut compiled = vec![];
+
+        for (rule, (_, pattern)) in rules.iter().enumerate() {
+            let rule_nfa = parse(pattern)
+                .and_then(|ast| definitions.resolve(&ast))
+                .and_then(|ast| compiler.compile_rule(&ast))
+                .map_err(|error| RuleError { rule, error })?;
+
+            compiled.push(rule_nfa);
+        }
+
+        let tokens = rules.iter().map(|(name, _)| name.to_string()).collect();
+        let line_starts = compiled.iter().any(|rule| rule.line_start);
+
+        // Every rule starts from the start states of its modes, and from
+        // those at the start of a line, which follow them.
+        let starts: Vec<Vec<usize>> = compiled
+            .iter()
+            .zip(modes)
+            .map(|(rule, modes)| {
+                let at_line_start = modes.iter().map(|mode| mode + mode_count);
+
+                match (rule.end_of_input, rule.line_start, line_starts) {
+                    (true, _, _) => vec![],
+                    (false, true, _) => at_line_start.collect(),
+                    (false, false, true) => modes.iter().copied().chain(at_line_start).collect(),
+                    (false, false, false) => modes.clone(),
+                }
+            })
+            .collect();
+
+        let end_rules = (0..mode_count)
+            .map(|mode| {
+                compiled
+                    .iter()
+                    .zip(modes)
+                    .position(|(rule, modes)| rule.end_of_input && modes.contains(&mode))
+            })
+            .collect();
+
+        let contexts = compiled.iter().map(|rule| rule.context).collect();
+        let nfas = compiled.into_iter().map(|rule| rule.nfa).collect();
+
+        let mode_starts = (0..mode_count).map(|_| compiler.alloc(1)).collect();
+        let line_start_ids = match line_starts {
+            true => (0..mode_count).map(|_| compiler.alloc(1)).collect(),
+            false => vec![],
+        };
+
+        let mut nfa = LexerNfa::with_modes(tokens, nfas, &starts, mode_starts, line_start_ids);
+        nfa.set_contexts(contexts);
+        nfa.set_end_rules(end_rules);
+
+        Ok(nfa)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex::Span;
+
+    #[test]
+    fn empty_regex() {
+        let nfa = Nfa::from_regex("").unwrap();
+
+        assert!(nfa.is_match(""));
+        assert!(!nfa.is_match("a"));
+    }
+
+    #[test]
+    fn text() {
+        let nfa = Nfa::from_regex(r#" "if" "#).unwrap();
+
+        assert!(nfa.is_match("if"));
+        assert!(!nfa.is_match("i"));
+        assert!(!nfa.is_match("iff"));
+    }
+
+    #[test]
+    fn name_or_text() {
+        let nfa = Nfa::from_regex(r#" while "#).unwrap();
+
+        assert!(nfa.is_match("while"));
+        assert!(!nfa.is_match("whil"));
+    }
+
+    #[test]
+    fn or() {
+        let nfa = Nfa::from_regex(r#" if | else | while "#).unwrap();
+
+        assert!(nfa.is_match("if"));
+        assert!(nfa.is_match("else"));
+        assert!(nfa.is_match("while"));
+        assert!(!nfa.is_match("for"));
+    }
+
+    #[test]
+    fn concat() {
+        let nfa = Nfa::from_regex(r#" "r"."l"."e"."x" "#).unwrap();
+
+        assert!(nfa.is_match("rlex"));
+        assert!(!nfa.is_match("rle"));
+    }
+
+    #[test]
+    fn star_plus_question() {
+        let star = Nfa::from_regex(r#" a* "#).unwrap();
+        let plus = Nfa::from_regex(r#" a+ "#).unwrap();
+        let question = Nfa::from_regex(r#" a? "#).unwrap();
+
+        assert!(star.is_match(""));
+        assert!(star.is_match("aaa"));
+
+        assert!(!plus.is_match(""));
+        assert!(plus.is_match("a"));
+        assert!(plus.is_match("aaa"));
+
+        assert!(question.is_match(""));
+        assert!(question.is_match("a"));
+        assert!(!question.is_match("aa"));
+    }
+
+    #[test]
+    fn char_dash() {
+        let nfa = Nfa::from_regex(r#" a-c "#).unwrap();
+
+        assert!(nfa.is_match("a"));
+        assert!(nfa.is_match("b"));
+        assert!(nfa.is_match("c"));
+        assert!(!nfa.is_match("d"));
+    }
+
+    #[test]
+    fn number_dash() {
+        let nfa = Nfa::from_regex(r#" 2-4 "#).unwrap();
+
+        assert!(nfa.is_match("3"));
+        assert!(!nfa.is_match("5"));
+    }
+
+    #[test]
+    fn single_char_and_number() {
+        assert!(Nfa::from_regex(r#" a "#).unwrap().is_match("a"));
+        assert!(Nfa::from_regex(r#" 7 "#).unwrap().is_match("7"));
+    }
+
+    #[test]
+    fn eps() {
+        let nfa = Nfa::from_regex(r#" a . (b | eps) . c "#).unwrap();
+
+        assert!(nfa.is_match("abc"));
+        assert!(nfa.is_match("ac"));
+        assert!(!nfa.is_match("abbc"));
+    }
+
+    #[test]
+    fn identifier_regex() {
+        let nfa = Nfa::from_regex(r#" (a-z)+.(a-z | 0-9 | _ )* "#).unwrap();
+
+        assert!(nfa.is_match("rlex"));
+        assert!(nfa.is_match("to_postfix"));
+        assert!(nfa.is_match("of_2"));
+        assert!(!nfa.is_match("_private"));
+        assert!(!nfa.is_match("2d"));
+    }
+
+    #[test]
+    fn floating_point_number_regex() {
+        let nfa = Nfa::from_regex(r#" 0-9+.".".0-9+ "#).unwrap();
+
+        assert!(nfa.is_match("3.14"));
+        assert!(nfa.is_match("10.0"));
+        assert!(!nfa.is_match("10."));
+        assert!(!nfa.is_match(".5"));
+    }
+
+    #[test]
+    fn implicit_concat() {
+        let nfa = Nfa::from_regex(r#" "0x" (0-9 | a-f)+ "#).unwrap();
+
+        assert!(nfa.is_match("0x1f"));
+        assert!(!nfa.is_match("0x"));
+        assert!(!nfa.is_match("1f"));
+    }
+
+    #[test]
+    fn bounded_repeat() {
+        let nfa = Nfa::from_regex(r#" (a-b){2,3} "#).unwrap();
+
+        assert!(!nfa.is_match("a"));
+        assert!(nfa.is_match("ab"));
+        assert!(nfa.is_match("bab"));
+        assert!(!nfa.is_match("abab"));
+    }
+
+    #[test]
+    fn definitions() {
+        let mut definitions = Definitions::new();
+        definitions.define("digit", "0-9").unwrap();
+        definitions.define("hex", "digit | a-f").unwrap();
+
+        let nfa = Nfa::from_regex_with(r#" "0x" hex+ "#, &definitions).unwrap();
+
+        assert!(nfa.is_match("0x1f"));
+        assert!(!nfa.is_match("0xhex"));
+    }
+
+    #[test]
+    fn unicode_literals() {
+        let nfa = Nfa::from_regex(r#" "你好" | اگر | "🚀"+ "#).unwrap();
+
+        assert!(nfa.is_match("你好"));
+        assert!(nfa.is_match("اگر"));
+        assert!(nfa.is_match("🚀🚀"));
+        assert!(!nfa.is_match("你"));
+        assert!(!nfa.is_match("اگ"));
+    }
+
+    #[test]
+    fn unicode_ranges() {
+        let nfa = Nfa::from_regex(r#" α-ω+ ۰-۹ "#).unwrap();
+
+        assert!(nfa.is_match("λογος۳"));
+        assert!(!nfa.is_match("abc۳"));
+        assert!(!nfa.is_match("λ3"));
+    }
+
+    #[test]
+    fn classes() {
+        let nfa = Nfa::from_regex(r#" [a-zA-Z_][a-zA-Z0-9_]* "#).unwrap();
+
+        assert!(nfa.is_match("Nfa_2"));
+        assert!(nfa.is_match("_"));
+        assert!(!nfa.is_match("2d"));
+    }
+
+    #[test]
+    fn negated_class_and_escapes() {
+        let nfa = Nfa::from_regex(r#" "\"" ([^"\\\n] | "\\" [nt"\\])* "\"" "#).unwrap();
+
+        assert!(nfa.is_match(r#""""#));
+        assert!(nfa.is_match(r#""a\"b\n😀""#));
+        assert!(!nfa.is_match(r#""a"b""#));
+        assert!(!nfa.is_match("\"a\nb\""));
+    }
+
+    #[test]
+    fn unicode_escape() {
+        let nfa = Nfa::from_regex(r#" "\u{1F600}"+ [\u{3b1}-\u{3c9}] "#).unwrap();
+
+        assert!(nfa.is_match("😀😀λ"));
+        assert!(!nfa.is_match("😀a"));
+    }
+
+    #[test]
+    fn exact_and_open_repeat() {
+        let nfa = Nfa::from_regex(r#" "0x" [0-9a-f]{4} "#).unwrap();
+
+        assert!(nfa.is_match("0x00ff"));
+        assert!(!nfa.is_match("0x0ff"));
+        assert!(!nfa.is_match("0x000ff"));
+
+        let nfa = Nfa::from_regex(r#" a{2,} "#).unwrap();
+
+        assert!(!nfa.is_match("a"));
+        assert!(nfa.is_match("aa"));
+        assert!(nfa.is_match("aaaaa"));
+    }
+
+    #[test]
+    fn zero_repeat_matches_empty() {
+        let nfa = Nfa::from_regex(r#" a{0} b "#).unwrap();
+
+        assert!(nfa.is_match("b"));
+        assert!(!nfa.is_match("ab"));
+    }
+
+    #[test]
+    fn repeat_limit() {
+        assert_eq!(
+            Nfa::from_regex(r#" a . b{1,100000} "#).err(),
+            Some(RegexError::RepeatTooLarge(REPEAT_LIMIT, Span::new(6, 16)))
+        );
+
+        let ast = parse(r#" a{5} "#).unwrap();
+
+        assert_eq!(
+            Nfa::from_ast_with_limit(&ast, 4).err(),
+            Some(RegexError::RepeatTooLarge(4, Span::new(2, 5)))
+        );
+        assert!(Nfa::from_ast_with_limit(&ast, 5).is_ok());
+    }
+
+    #[test]
+    fn repeat_limit_of_nested_repeats() {
+        assert_eq!(
+            Nfa::from_regex(r#" "x"{1000}{1000}{1000} "#).err(),
+            Some(RegexError::RepeatTooLarge(REPEAT_LIMIT, Span::new(10, 16)))
+        );
+
+        let ast = parse(r#" (a{2} | b){3,} "#).unwrap();
+
+        assert_eq!(
+            Nfa::from_ast_with_limit(&ast, 5).err(),
+            Some(RegexError::RepeatTooLarge(5, Span::new(3, 6)))
+        );
+        assert!(Nfa::from_ast_with_limit(&ast, 6).is_ok());
+        assert!(Nfa::from_regex(r#" (a{10} b*){100} "#).is_ok());
+    }
+
+    #[test]
+    fn repeat_limit_in_definition() {
+        let mut definitions = Definitions::new();
+        definitions.define("huge", "a{2000}").unwrap();
+
+        assert_eq!(
+            Nfa::from_regex_with(r#" b huge "#, &definitions).err(),
+            Some(RegexError::RepeatTooLarge(REPEAT_LIMIT, Span::new(3, 7)))
+        );
+    }
+
+    #[test]
+    fn huge_range_is_a_single_transition() {
+        let nfa = Nfa::from_regex(r#" \u{0}-\u{10FFFF} "#);
+
+        assert!(nfa.is_err());
+
+        let nfa = Nfa::from_regex(r#" α-ω "#).unwrap();
+
+        assert_eq!(nfa.transitions().count(), 1);
+        assert!(nfa.is_match("λ"));
+    }
+
+    #[test]
+    fn class_is_a_single_transition() {
+        let nfa = Nfa::from_regex(r#" [\u{0}-\u{10FFFF}] "#).unwrap();
+
+        assert_eq!(nfa.transitions().count(), 1);
+        assert!(nfa.is_match("\u{10FFFF}"));
+    }
+
+    #[test]
+    fn state_ids_do_not_collide() {
+        let nfa = Nfa::from_regex(r#" (a | b)* . "ab" . (c | eps) "#).unwrap();
+
+        assert!(nfa.is_match("ab"));
+        assert!(nfa.is_match("babab"));
+        assert!(nfa.is_match("aabc"));
+        assert!(!nfa.is_match("abcc"));
+    }
+
+    #[test]
+    fn malformed_postfix() {
+        let a = Spanned::new(Element::Char('a'), Span::new(0, 1));
+        let b = Spanned::new(Element::Char('b'), Span::new(2, 3));
+        let or = Spanned::new(Element::Or, Span::new(4, 5));
+        let digit = Spanned::new(Element::Number(1), Span::new(6, 7));
+        let dash = Spanned::new(Element::Dash, Span::new(8, 9));
+
+        assert_eq!(
+            Nfa::from_postfix(&[a, or]).err(),
+            Some(RegexError::MissingOperand(Span::new(4, 5)))
+        );
+        assert_eq!(
+            Nfa::from_postfix(&[a, b]).err(),
+            Some(RegexError::MissingOperator(Span::new(0, 1)))
+        );
+        assert_eq!(
+            Nfa::from_postfix(&[a, digit, dash]).err(),
+            Some(RegexError::InvalidRange(Span::new(0, 7)))
+        );
+    }
+
+    #[test]
+    fn trailing_context() {
+        let nfa = LexerNfa::from_rules(&[
+            ("Int", r#" 0-9+ / ".." "#),
+            ("If", r#" "if" / " "* "(" "#),
+            ("Ident", "a-z+"),
+        ])
+        .unwrap();
+
+        assert_eq!(nfa.context_of(0), Some(TrailingContext::Tail(2)));
+        assert_eq!(nfa.context_of(1), Some(TrailingContext::Head(2)));
+        assert_eq!(nfa.context_of(2), None);
+    }
+
+    #[test]
+    fn trailing_context_of_huge_repeats() {
+        for pattern in &[
+            r#" "aa"{18446744073709551615} / "b" "#,
+            r#" "a"{9223372036854775808} "a"{9223372036854775808} / "b" "#,
+        ] {
+            assert!(matches!(
+                LexerNfa::from_rules(&[("A", pattern)])
+                    .err()
+                    .map(|e| e.error),
+                Some(RegexError::RepeatTooLarge(REPEAT_LIMIT, _))
+            ));
+        }
+    }
+
+    #[test]
+    fn trailing_context_errors() {
+        let error_of = |pattern| {
+            LexerNfa::from_rules(&[("A", pattern)])
+                .err()
+                .map(|e| e.error)
+        };
+
+        assert_eq!(
+            error_of(" a+ / b* "),
+            Some(RegexError::VariableTrailingContext(Span::new(4, 5)))
+        );
+        assert_eq!(
+            error_of(" a* / b "),
+            Some(RegexError::EmptyTrailingHead(Span::new(4, 5)))
+        );
+        assert_eq!(
+            error_of(" (a / b) | c "),
+            Some(RegexError::MisplacedTrailingContext(Span::new(4, 5)))
+        );
+        assert_eq!(
+            error_of(" a / b / c "),
+            Some(RegexError::MisplacedTrailingContext(Span::new(3, 4)))
+        );
+        assert_eq!(
+            Nfa::from_regex(" a / b ").err(),
+            Some(RegexError::MisplacedTrailingContext(Span::new(3, 4)))
+        );
+
+        let mut definitions = Definitions::new();
+        definitions.define("call", r#" a / "(" "#).unwrap();
+
+        assert_eq!(
+            LexerNfa::from_rules_with(&[("A", " call b ")], &definitions)
+                .err()
+                .map(|e| e.error),
+            Some(RegexError::MisplacedTrailingContext(Span::new(1, 5)))
+        );
+    }
+
+    #[test]
+    fn anchors() {
+        let nfa = LexerNfa::from_rules(&[
+            ("Directive", r##" ^ "#" a-z+ "##),
+            ("Last", " a-z+ $ "),
+            ("Eof", "<<EOF>>"),
+            ("Word", "a-z+"),
+        ])
+        .unwrap();
+
+        assert_ne!(nfa.line_starts(), nfa.starts());
+        assert_eq!(nfa.context_of(1), Some(TrailingContext::Tail(1)));
+        assert_eq!(nfa.end_rule(0), Some(2));
+
+        let nfa = LexerNfa::from_rules(&[("Word", "a-z+")]).unwrap();
+
+        assert_eq!(nfa.line_starts(), nfa.starts());
+        assert_eq!(nfa.end_rule(0), None);
+    }
+
+    #[test]
+    fn misplaced_anchors() {
+        let error_of = |pattern| {
+            LexerNfa::from_rules(&[("A", pattern)])
+                .err()
+                .map(|e| e.error)
+        };
+
+        assert_eq!(
+            error_of(" a ^ b "),
+            Some(RegexError::MisplacedAnchor(Span::new(3, 4)))
+        );
+        assert_eq!(
+            error_of(" a $ | b "),
+            Some(RegexError::MisplacedAnchor(Span::new(3, 4)))
+        );
+        assert_eq!(
+            error_of(" a / b $ "),
+            Some(RegexError::MisplacedAnchor(Span::new(7, 8)))
+        );
+        assert_eq!(
+            error_of(" a <<EOF>> "),
+            Some(RegexError::MisplacedAnchor(Span::new(3, 10)))
+        );
+        assert_eq!(
+            Nfa::from_regex(" ^ a ").err(),
+            Some(RegexError::MisplacedAnchor(Span::new(1, 2)))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "every rule needs its modes")]
+    fn rules_without_modes() {
+        let _ = LexerNfa::from_rules_in_modes(&[("A", "a")], &[], 1, &Definitions::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "mode index out of range")]
+    fn rules_in_unknown_modes() {
+        let _ = LexerNfa::from_rules_in_modes(&[("A", "a")], &[vec![1]], 1, &Definitions::new());
+    }
+}
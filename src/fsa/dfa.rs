@@ -0,0 +1,331 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::{Lable, Nfa};
+use crate::regex::{next_char, prev_char};
+
+/// A deterministic automaton.
+///
+/// States are numbered from zero in the order they are discovered, so the
+/// start state of a Dfa built by `from_nfa` is always `0`. The transitions of
+/// a state are sorted, disjoint ranges of chars; a char outside all of them
+/// has no transition.
+///
+/// The Dfa of a lexer with several modes has a start state per mode, the
+/// first of which is the one `sid` returns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dfa {
+    starts: Vec<usize>,
+    // Accepting states, mapped to the index of the token they accept.
+    accepting: HashMap<usize, usize>,
+    transitions: Vec<Vec<(char, char, usize)>>,
+}
+
+/// Splits the chars into disjoint ranges such that every lable of `nfa`
+/// matches either all or none of the chars of a range. Ranges that no lable
+/// matches are left out.
+fn alphabet_of(nfa: &Nfa) -> Vec<(char, char)> {
+    let mut bounds = BTreeSet::new();
+    let mut covered = vec![];
+
+    for (_, lable, _) in nfa.transitions() {
+        let ranges = match lable {
+            Lable::Char(c) => vec![(*c, *c)],
+            Lable::Class(class) => class.ranges().to_vec(),
+            Lable::Eps => continue,
+        };
+
+        for (from, to) in ranges {
+            bounds.insert(from);
+            bounds.extend(next_char(to));
+            covered.push((from, to));
+        }
+    }
+
+    let bounds: Vec<char> = bounds.into_iter().collect();
+
+    bounds
+        .iter()
+        .enumerate()
+        .map(|(index, &from)| match bounds.get(index + 1) {
+            Some(&next) => (from, prev_char(next).unwrap()),
+            None => (from, char::MAX),
+        })
+        .filter(|&(from, _)| covered.iter().any(|&(lo, hi)| lo <= from && from <= hi))
+        .collect()
+}
+
+impl Dfa {
+    /// Builds the Dfa that accepts the same strings as `nfa`, by subset
+    /// construction.
+    ///
+    /// Every state of the Dfa stands for the epsilon closure of a set of
+    /// `nfa` states, and accepts token `0` if that set contains the finish
+    /// state.
+    pub fn from_nfa(nfa: &Nfa) -> Self {
+        let finish_id = nfa.fid();
+
+        Dfa::from_nfa_with(nfa, &[nfa.sid()], |state| {
+            if state == finish_id {
+                Some(0)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Builds a Dfa by subset construction, with a start state for each of
+    /// the `starts` of `nfa`. A state of the Dfa accepts the smallest token
+    /// that `token_of` gives for any of the `nfa` states it stands for.
+    pub(super) fn from_nfa_with<F>(nfa: &Nfa, starts: &[usize], token_of: F) -> Self
+    where
+        F: Fn(usize) -> Option<usize>,
+    {
+        let alphabet = alphabet_of(nfa);
+
+        let mut ids = HashMap::new();
+        let mut sets = vec![];
+        let mut transitions = vec![];
+
+        // Modes with the same rules share their start state.
+        let starts = starts
+            .iter()
+            .map(|&start| {
+                let set = nfa.closure(vec![start]);
+
+                *ids.entry(set.clone()).or_insert_with(|| {
+                    sets.push(set);
+                    sets.len() - 1
+                })
+            })
+            .collect();
+
+        while transitions.len() < sets.len() {
+            let set = sets[transitions.len()].clone();
+            let mut row: Vec<(char, char, usize)> = vec![];
+
+            for &(from, to) in &alphabet {
+                let moved: HashSet<usize> = set
+                    .iter()
+                    .flat_map(|state| nfa.targets(*state, from))
+                    .collect();
+
+                if moved.is_empty() {
+                    continue;
+                }
+
+                let target_set = nfa.closure(moved);
+
+                let target = match ids.get(&target_set) {
+                    Some(&target) => target,
+                    None => {
+                        let target = sets.len();
+
+                        ids.insert(target_set.clone(), target);
+                        sets.push(target_set);
+
+                        target
+                    }
+                };
+
+                // Neighbouring ranges that lead to the same state are merged.
+                match row.last_mut() {
+                    Some((_, last_to, last_target))
+                        if *last_target == target && next_char(*last_to) == Some(from) =>
+                    {
+                        *last_to = to
+                    }
+                    _ => row.push((from, to, target)),
+                }
+            }
+
+            transitions.push(row);
+        }
+
+        let accepting = sets
+            .iter()
+            .enumerate()
+            .filter_map(|(id, set)| {
+                let token = set.iter().filter_map(|&state| token_of(state)).min()?;
+
+                Some((id, token))
+            })
+            .collect();
+
+        Dfa {
+            starts,
+            accepting,
+            transitions,
+        }
+    }
+
+    pub(super) fn from_parts(
+        starts: Vec<usize>,
+        accepting: HashMap<usize, usize>,
+        transitions: Vec<Vec<(char, char, usize)>>,
+    ) -> Self {
+        Dfa {
+            starts,
+            accepting,
+            transitions,
+        }
+    }
+
+    pub fn sid(&self) -> usize {
+        self.starts[0]
+    }
+
+    /// The start state of every mode, in mode order.
+    pub fn starts(&self) -> &[usize] {
+        &self.starts
+    }
+
+    /// The accepting states, mapped to the index of the token they accept.
+    pub fn accepting(&self) -> &HashMap<usize, usize> {
+        &self.accepting
+    }
+
+    pub fn is_accepting(&self, state_id: usize) -> bool {
+        self.accepting.contains_key(&state_id)
+    }
+
+    /// The index of the token `state_id` accepts, if it is accepting.
+    pub fn token_of(&self, state_id: usize) -> Option<usize> {
+        self.accepting.get(&state_id).copied()
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// The transitions out of `state_id` as sorted `(from, to, target)`
+    /// ranges.
+    pub fn transitions(&self, state_id: usize) -> &[(char, char, usize)] {
+        &self.transitions[state_id]
+    }
+
+    /// The state reached from `state_id` on `c`, if there is a transition.
+    pub fn next(&self, state_id: usize, c: char) -> Option<usize> {
+        let row = &self.transitions[state_id];
+
+        row.binary_search_by(|&(from, to, _)| {
+            if to < c {
+                std::cmp::Ordering::Less
+            } else if from > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .ok()
+        .map(|index| row[index].2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dfa_accepts(dfa: &Dfa, input: &str) -> bool {
+        let mut state = dfa.sid();
+
+        for c in input.chars() {
+            match dfa.next(state, c) {
+                Some(next) => state = next,
+                None => return false,
+            }
+        }
+
+        dfa.is_accepting(state)
+    }
+
+    /// Every string over `alphabet` of at most `length` chars.
+    fn strings_over(alphabet: &[char], length: usize) -> Vec<String> {
+        let mut strings = vec![String::new()];
+        let mut last = vec![String::new()];
+
+        for _ in 0..length {
+            last = last
+                .iter()
+                .flat_map(|prefix| {
+                    alphabet.iter().map(move |c| {
+                        let mut string = prefix.clone();
+                        string.push(*c);
+
+                        string
+                    })
+                })
+                .collect();
+
+            strings.extend(last.iter().cloned());
+        }
+
+        strings
+    }
+
+    fn assert_same_language(regex: &str, alphabet: &[char], length: usize) {
+        let nfa = Nfa::from_regex(regex).unwrap();
+        let dfa = Dfa::from_nfa(&nfa);
+
+        for input in strings_over(alphabet, length) {
+            assert_eq!(
+                dfa_accepts(&dfa, &input),
+                nfa.is_match(&input),
+                "{} on {:?}",
+                regex,
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn same_language_as_nfa() {
+        assert_same_language(r#" (a | b)* a b b "#, &['a', 'b', 'c'], 6);
+        assert_same_language(r#" "if" | "ifa" | a-z+ "#, &['i', 'f', 'a', '0'], 4);
+        assert_same_language(r#" 0-9+ ("." 0-9+)? "#, &['0', '5', '.', 'x'], 5);
+        assert_same_language(r#" [a-c] [^b]{1,2} "#, &['a', 'b', 'c', 'd'], 4);
+        assert_same_language(r#" eps "#, &['a'], 2);
+        assert_same_language(r#" (α-ω | ß)+ "#, &['α', 'λ', 'ß', 'a'], 3);
+    }
+
+    #[test]
+    fn start_and_accepting_states() {
+        let dfa = Dfa::from_nfa(&Nfa::from_regex(r#" "ab" "#).unwrap());
+
+        assert_eq!(dfa.sid(), 0);
+        assert_eq!(dfa.state_count(), 3);
+        assert!(!dfa.is_accepting(0));
+
+        let after_a = dfa.next(0, 'a').unwrap();
+        let after_ab = dfa.next(after_a, 'b').unwrap();
+
+        assert_eq!(dfa.accepting(), &vec![(after_ab, 0)].into_iter().collect());
+        assert_eq!(dfa.token_of(after_ab), Some(0));
+        assert_eq!(dfa.next(0, 'b'), None);
+        assert_eq!(dfa.next(after_ab, 'a'), None);
+    }
+
+    #[test]
+    fn overlapping_lables_are_split() {
+        let dfa = Dfa::from_nfa(&Nfa::from_regex(r#" a-z "1" | d-f "2" "#).unwrap());
+
+        assert_eq!(
+            dfa.transitions(0)
+                .iter()
+                .map(|&(from, to, _)| (from, to))
+                .collect::<Vec<_>>(),
+            vec![('a', 'c'), ('d', 'f'), ('g', 'z')]
+        );
+        assert_eq!(dfa.next(0, 'a'), dfa.next(0, 'z'));
+        assert_ne!(dfa.next(0, 'a'), dfa.next(0, 'e'));
+    }
+
+    #[test]
+    fn large_ranges_stay_compact() {
+        let dfa = Dfa::from_nfa(&Nfa::from_regex(r#" [^"]* "#).unwrap());
+
+        assert_eq!(dfa.state_count(), 2);
+        assert_eq!(dfa.transitions(0).len(), 2);
+        assert_eq!(dfa.next(0, '"'), None);
+        assert!(dfa.next(0, '\u{10FFFF}').is_some());
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use super::{Dfa, Lable, LexerDfa, LexerNfa, Nfa};
+use crate::regex::next_char;
+
+/// An automaton on its way to Graphviz DOT source.
+struct Graph {
+    // The start state of every mode.
+    starts: Vec<usize>,
+    states: BTreeSet<usize>,
+    // Accepting states, with the name of what they accept, if any.
+    accepting: BTreeMap<usize, Option<String>>,
+    // The chars moving from a state to another, as unsorted ranges.
+    edges: BTreeMap<(usize, usize), Vec<(char, char)>>,
+    eps_edges: BTreeSet<(usize, usize)>,
+}
+
+impl Graph {
+    fn new(starts: &[usize]) -> Self {
+        Graph {
+            starts: starts.to_vec(),
+            states: starts.iter().copied().collect(),
+            accepting: BTreeMap::new(),
+            edges: BTreeMap::new(),
+            eps_edges: BTreeSet::new(),
+        }
+    }
+
+    fn of_nfa(nfa: &Nfa, starts: &[usize]) -> Self {
+        let mut graph = Graph::new(starts);
+
+        for (state, lable, targets) in nfa.transitions() {
+            graph.states.insert(state);
+
+            for &target in targets {
+                graph.states.insert(target);
+
+                match lable {
+                    Lable::Char(c) => graph.add_edge(state, target, &[(*c, *c)]),
+                    Lable::Class(class) => graph.add_edge(state, target, class.ranges()),
+                    Lable::Eps => {
+                        graph.eps_edges.insert((state, target));
+                    }
+                }
+            }
+        }
+
+        graph
+    }
+
+    fn of_dfa(dfa: &Dfa) -> Self {
+        let mut graph = Graph::new(dfa.starts());
+
+        for state in 0..dfa.state_count() {
+            graph.states.insert(state);
+
+            for &(from, to, target) in dfa.transitions(state) {
+                graph.add_edge(state, target, &[(from, to)]);
+            }
+        }
+
+        graph
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, ranges: &[(char, char)]) {
+        self.edges
+            .entry((from, to))
+            .or_default()
+            .extend_from_slice(ranges);
+    }
+
+    fn render(&self, name: &str) -> String {
+        let mut out = String::new();
+
+        writeln!(out, "digraph {} {{", name).unwrap();
+        out.push_str("    rankdir=LR;\n");
+        out.push_str("    node [shape=circle];\n");
+
+        if let [start] = self.starts[..] {
+            out.push_str("    start [shape=point];\n");
+            writeln!(out, "    start -> {};", start).unwrap();
+        } else {
+            for (mode, start) in self.starts.iter().enumerate() {
+                writeln!(out, "    start{} [shape=point];", mode).unwrap();
+                writeln!(out, "    start{} -> {};", mode, start).unwrap();
+            }
+        }
+
+        for state in &self.states {
+            match self.accepting.get(state) {
+                Some(Some(name)) => writeln!(
+                    out,
+                    "    {} [shape=doublecircle, label=\"{}\\n{}\"];",
+                    state,
+                    state,
+                    escape(name)
+                )
+                .unwrap(),
+                Some(None) => writeln!(out, "    {} [shape=doublecircle];", state).unwrap(),
+                None => writeln!(out, "    {};", state).unwrap(),
+            }
+        }
+
+        for ((from, to), ranges) in &self.edges {
+            writeln!(
+                out,
+                "    {} -> {} [label=\"{}\"];",
+                from,
+                to,
+                range_label(ranges)
+            )
+            .unwrap();
+        }
+
+        for (from, to) in &self.eps_edges {
+            writeln!(out, "    {} -> {} [label=\"ε\", style=dashed];", from, to).unwrap();
+        }
+
+        out.push_str("}\n");
+
+        out
+    }
+}
+
+/// `text` escaped for a quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// `c` as shown on an edge, with spaces, dashes, commas and control chars
+/// spelled out so that they can be told apart from the separators of the
+/// label.
+fn char_label(c: char) -> String {
+    match c {
+        ' ' => "' '".to_string(),
+        '-' => "'-'".to_string(),
+        ',' => "','".to_string(),
+        c if c.is_control() || c.is_whitespace() => escape(&c.escape_default().to_string()),
+        c => escape(&c.to_string()),
+    }
+}
+
+/// The label of an edge taken on `ranges`, with overlapping and adjacent
+/// ranges merged, such as `a-z, _`.
+fn range_label(ranges: &[(char, char)]) -> String {
+    let mut ranges = ranges.to_vec();
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(char, char)> = vec![];
+
+    for (from, to) in ranges {
+        match merged.last_mut() {
+            Some((_, last)) if Some(from) <= next_char(*last) => *last = (*last).max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+
+    let parts: Vec<String> = merged
+        .into_iter()
+        .map(|(from, to)| {
+            if from == to {
+                char_label(from)
+            } else {
+                format!("{}-{}", char_label(from), char_label(to))
+            }
+        })
+        .collect();
+
+    parts.join(", ")
+}
+
+impl Nfa {
+    /// Renders the automaton as Graphviz DOT source, for `dot -Tsvg`.
+    ///
+    /// The finish state is drawn with a double circle and epsilon edges are
+    /// dashed.
+    pub fn to_dot(&self) -> String {
+        let mut graph = Graph::of_nfa(self, &[self.sid()]);
+
+        graph.states.insert(self.fid());
+        graph.accepting.insert(self.fid(), None);
+
+        graph.render("nfa")
+    }
+}
+
+impl Dfa {
+    /// Renders the automaton as Graphviz DOT source, with accepting states
+    /// drawn with a double circle.
+    pub fn to_dot(&self) -> String {
+        let mut graph = Graph::of_dfa(self);
+
+        for state in 0..self.state_count() {
+            if self.is_accepting(state) {
+                graph.accepting.insert(state, None);
+            }
+        }
+
+        graph.render("dfa")
+    }
+}
+
+/// The name shown for the accepting states of `rule`: its token name, or
+/// its index for rules without one.
+fn rule_name(tokens: &[String], rule: usize) -> String {
+    match tokens[rule].as_str() {
+        "" => format!("rule {}", rule),
+        name => name.to_string(),
+    }
+}
+
+impl LexerNfa {
+    /// Renders the automaton as Graphviz DOT source, labelling the finish
+    /// state of every rule with its token name. A lexer with several modes
+    /// gets a `startN` arrow into the start state of mode `N`, and the start
+    /// states at the start of a line, if any, are numbered after them.
+    pub fn to_dot(&self) -> String {
+        let mut starts = self.starts().to_vec();
+
+        if self.line_starts() != self.starts() {
+            starts.extend_from_slice(self.line_starts());
+        }
+
+        let mut graph = Graph::of_nfa(self.nfa(), &starts);
+        let states: Vec<usize> = graph.states.iter().copied().collect();
+
+        for state in states {
+            if let Some(rule) = self.token_of(state) {
+                graph
+                    .accepting
+                    .insert(state, Some(rule_name(self.tokens(), rule)));
+            }
+        }
+
+        graph.render("nfa")
+    }
+}
+
+impl LexerDfa {
+    /// Renders the automaton as Graphviz DOT source, labelling every
+    /// accepting state with the token name of the rule that wins it.
+    pub fn to_dot(&self) -> String {
+        let mut graph = Graph::of_dfa(self.dfa());
+
+        for state in 0..self.dfa().state_count() {
+            if let Some(rule) = self.token_of(state) {
+                graph
+                    .accepting
+                    .insert(state, Some(rule_name(self.tokens(), rule)));
+            }
+        }
+
+        graph.render("dfa")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex::CharClass;
+
+    #[test]
+    fn nfa_of_star() {
+        let nfa = Nfa::of_star(Nfa::of_char('a', 0), 2);
+
+        assert_eq!(
+            nfa.to_dot(),
+            "digraph nfa {
+    rankdir=LR;
+    node [shape=circle];
+    start [shape=point];
+    start -> 2;
+    0;
+    1;
+    2;
+    3 [shape=doublecircle];
+    0 -> 1 [label=\"a\"];
+    1 -> 0 [label=\"ε\", style=dashed];
+    1 -> 3 [label=\"ε\", style=dashed];
+    2 -> 0 [label=\"ε\", style=dashed];
+    2 -> 3 [label=\"ε\", style=dashed];
+}
+"
+        );
+    }
+
+    #[test]
+    fn char_edges_collapse_into_ranges() {
+        let mut nfa = Nfa::of_char('a', 0);
+
+        for c in "bcdxz".chars() {
+            nfa.insert_transition(0, Lable::Char(c), 1);
+        }
+
+        nfa.insert_transition(0, Lable::Class(CharClass::of_range('y', 'y')), 1);
+
+        assert!(nfa.to_dot().contains("    0 -> 1 [label=\"a-d, x-z\"];\n"));
+    }
+
+    #[test]
+    fn labels_are_escaped() {
+        assert_eq!(
+            range_label(&[('"', '"'), ('\\', '\\'), (' ', ' '), ('\n', '\n')]),
+            "\\\\n, ' ', \\\", \\\\"
+        );
+        assert_eq!(range_label(&[('-', 'z')]), "'-'-z");
+        assert_eq!(range_label(&[('-', '-'), ('a', 'c')]), "'-', a-c");
+        assert_eq!(range_label(&[(',', ','), ('a', 'a')]), "',', a");
+    }
+
+    #[test]
+    fn lexer_dfa() {
+        let nfa = LexerNfa::from_rules(&[("If", r#" "if" "#), ("", "a-z+")]).unwrap();
+        let mut dfa = LexerDfa::from_nfa(&nfa);
+        dfa.minimize();
+
+        let dot = dfa.to_dot();
+
+        assert!(dot.starts_with("digraph dfa {\n"));
+        assert!(dot.contains("    3 [shape=doublecircle, label=\"3\\nIf\"];\n"));
+        assert!(dot.contains("    1 [shape=doublecircle, label=\"1\\nrule 1\"];\n"));
+        assert!(dot.contains("    0 -> 1 [label=\"a-h, j-z\"];\n"));
+        assert_eq!(dot.matches("doublecircle").count(), 3);
+
+        let nfa_dot = nfa.to_dot();
+
+        assert!(nfa_dot.contains("\\nIf\"];"));
+        assert!(nfa_dot.contains("\\nrule 1\"];"));
+    }
+
+    #[test]
+    fn plain_dfa() {
+        let dfa = Dfa::from_nfa(&Nfa::of_text("ab", 0));
+
+        assert_eq!(
+            dfa.to_dot(),
+            "digraph dfa {
+    rankdir=LR;
+    node [shape=circle];
+    start [shape=point];
+    start -> 0;
+    0;
+    1;
+    2 [shape=doublecircle];
+    0 -> 1 [label=\"a\"];
+    1 -> 2 [label=\"b\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn start_of_every_mode() {
+        let nfa = LexerNfa::from_rules_in_modes(
+            &[("A", "a"), ("B", "b")],
+            &[vec![0], vec![1]],
+            2,
+            &crate::regex::Definitions::new(),
+        )
+        .unwrap();
+        let dfa = LexerDfa::from_nfa(&nfa);
+
+        for dot in &[nfa.to_dot(), dfa.to_dot()] {
+            assert!(!dot.contains("    start [shape=point];\n"));
+            assert!(dot.contains("    start0 [shape=point];\n"));
+            assert!(dot.contains("    start1 [shape=point];\n"));
+        }
+
+        assert!(dfa
+            .to_dot()
+            .contains(&format!("    start1 -> {};\n", dfa.starts()[1])));
+    }
+
+    #[test]
+    fn start_at_line_start() {
+        let nfa = LexerNfa::from_rules(&[("A", "^ a"), ("B", "b")]).unwrap();
+        let dot = nfa.to_dot();
+
+        assert!(dot.contains(&format!("    start1 -> {};\n", nfa.line_starts()[0])));
+        assert_eq!(dot.matches("[shape=point]").count(), 2);
+    }
+}
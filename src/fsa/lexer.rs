@@ -0,0 +1,451 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use super::{Dfa, Lable, Nfa};
+use crate::regex::RegexError;
+
+/// An error in the pattern of one of the rules of a lexer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleError {
+    /// Index of the rule whose pattern is wrong.
+    pub rule: usize,
+    /// The error, with a span into the pattern of the rule.
+    pub error: RegexError,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rule {}: {}", self.rule, self.error)
+    }
+}
+
+impl Error for RuleError {}
+
+/// The part of the text matched by a rule with trailing context that is its
+/// token. The rest is left for the next match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrailingContext {
+    /// The first this many chars.
+    Head(usize),
+    /// All but the last this many chars.
+    Tail(usize),
+}
+
+impl TrailingContext {
+    /// The length in bytes of the token in `text`, as matched by the rule.
+    pub fn token_len(self, text: &str) -> usize {
+        match self {
+            TrailingContext::Head(count) => text
+                .char_indices()
+                .nth(count)
+                .map_or(text.len(), |(index, _)| index),
+            TrailingContext::Tail(0) => text.len(),
+            TrailingContext::Tail(count) => text
+                .char_indices()
+                .rev()
+                .nth(count - 1)
+                .map_or(0, |(index, _)| index),
+        }
+    }
+}
+
+/// The Nfas of several token rules joined by a shared start state.
+///
+/// Each rule keeps its own finish state, which records the index of the
+/// rule it belongs to. A lexer with several modes has a start state per mode,
+/// joined to the rules active in that mode. When some rules only match at
+/// the start of a line, every mode has a second start state for it.
+pub struct LexerNfa {
+    // The start of `nfa` is the start state of the first mode. Its finish id
+    // has no meaning, the finish states of the rules are in `accepting`
+    // instead.
+    nfa: Nfa,
+    accepting: HashMap<usize, usize>,
+    tokens: Vec<String>,
+    // The start states of the modes, followed by those at the start of a
+    // line, if any.
+    starts: Vec<usize>,
+    mode_count: usize,
+    contexts: Vec<Option<TrailingContext>>,
+    end_rules: Vec<Option<usize>>,
+}
+
+impl LexerNfa {
+    /// Joins the Nfa of every rule, in order, to a new start state with id
+    /// `start_id`. The ids of all the Nfas must be distinct.
+    pub fn new(tokens: Vec<String>, rules: Vec<Nfa>, start_id: usize) -> Self {
+        let modes = vec![vec![0]; rules.len()];
+
+        LexerNfa::with_modes(tokens, rules, &modes, vec![start_id], vec![])
+    }
+
+    /// Joins the Nfa of every rule, in order, to the start states it is
+    /// given in `modes` as indices into `start_ids`, the start states of the
+    /// modes, followed by `line_start_ids`, their start states at the start
+    /// of a line. The latter may be left empty if no rule needs them.
+    pub fn with_modes(
+        tokens: Vec<String>,
+        rules: Vec<Nfa>,
+        modes: &[Vec<usize>],
+        start_ids: Vec<usize>,
+        line_start_ids: Vec<usize>,
+    ) -> Self {
+        assert_eq!(tokens.len(), rules.len(), "every rule needs a token name");
+        assert_eq!(modes.len(), rules.len(), "every rule needs its modes");
+
+        assert!(
+            line_start_ids.is_empty() || line_start_ids.len() == start_ids.len(),
+            "every mode needs a start state at the start of a line"
+        );
+
+        let mode_count = start_ids.len();
+        let starts: Vec<usize> = start_ids.into_iter().chain(line_start_ids).collect();
+
+        let mut nfa = Nfa::with_ids(starts[0], starts[0]);
+        let mut accepting = HashMap::new();
+
+        for (index, rule) in rules.into_iter().enumerate() {
+            for &start in &modes[index] {
+                nfa.insert_transition(starts[start], Lable::Eps, rule.sid());
+            }
+
+            accepting.entry(rule.fid()).or_insert(index);
+            nfa.absorb(rule);
+        }
+
+        LexerNfa {
+            nfa,
+            accepting,
+            contexts: vec![None; tokens.len()],
+            tokens,
+            starts,
+            mode_count,
+            end_rules: vec![None; mode_count],
+        }
+    }
+
+    pub fn sid(&self) -> usize {
+        self.nfa.sid()
+    }
+
+    /// The start state of every mode, in mode order.
+    pub fn starts(&self) -> &[usize] {
+        &self.starts[..self.mode_count]
+    }
+
+    /// The start state of every mode at the start of a line, which is the
+    /// usual one unless some rule needs its own.
+    pub fn line_starts(&self) -> &[usize] {
+        if self.starts.len() > self.mode_count {
+            &self.starts[self.mode_count..]
+        } else {
+            self.starts()
+        }
+    }
+
+    /// The joined automaton. Use `token_of` rather than its finish id to
+    /// find accepting states.
+    pub fn nfa(&self) -> &Nfa {
+        &self.nfa
+    }
+
+    /// The index of the rule that `state_id` is the finish state of.
+    pub fn token_of(&self, state_id: usize) -> Option<usize> {
+        self.accepting.get(&state_id).copied()
+    }
+
+    /// The token names, in rule order.
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// The trailing context of `rule`, if it has one.
+    pub fn context_of(&self, rule: usize) -> Option<TrailingContext> {
+        self.contexts[rule]
+    }
+
+    /// Sets the trailing context of every rule, in rule order.
+    pub fn set_contexts(&mut self, contexts: Vec<Option<TrailingContext>>) {
+        assert_eq!(
+            contexts.len(),
+            self.tokens.len(),
+            "every rule needs a context"
+        );
+
+        self.contexts = contexts;
+    }
+
+    /// The rule to run when the input runs out in `mode`, if any.
+    pub fn end_rule(&self, mode: usize) -> Option<usize> {
+        self.end_rules[mode]
+    }
+
+    /// Sets the rule to run at the end of the input in every mode, in mode
+    /// order.
+    pub fn set_end_rules(&mut self, end_rules: Vec<Option<usize>>) {
+        assert_eq!(
+            end_rules.len(),
+            self.mode_count,
+            "every mode needs an end rule"
+        );
+
+        self.end_rules = end_rules;
+    }
+}
+
+/// The deterministic automaton of a set of token rules.
+///
+/// Every accepting state records the rule that wins it: when the strings
+/// reaching a state match several rules, the earliest of them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LexerDfa {
+    dfa: Dfa,
+    tokens: Vec<String>,
+    mode_count: usize,
+    contexts: Vec<Option<TrailingContext>>,
+    end_rules: Vec<Option<usize>>,
+}
+
+impl LexerDfa {
+    pub fn from_nfa(nfa: &LexerNfa) -> Self {
+        LexerDfa {
+            dfa: Dfa::from_nfa_with(&nfa.nfa, &nfa.starts, |state| nfa.token_of(state)),
+            tokens: nfa.tokens.clone(),
+            mode_count: nfa.mode_count,
+            contexts: nfa.contexts.clone(),
+            end_rules: nfa.end_rules.clone(),
+        }
+    }
+
+    /// Merges equivalent states, never merging states that accept different
+    /// tokens, and returns the number of states removed.
+    pub fn minimize(&mut self) -> usize {
+        self.dfa.minimize()
+    }
+
+    /// The underlying Dfa, whose accepting states map to rule indices.
+    pub fn dfa(&self) -> &Dfa {
+        &self.dfa
+    }
+
+    pub fn sid(&self) -> usize {
+        self.dfa.sid()
+    }
+
+    /// The start state of every mode, in mode order.
+    pub fn starts(&self) -> &[usize] {
+        &self.dfa.starts()[..self.mode_count]
+    }
+
+    /// The start state of every mode at the start of a line, which is the
+    /// usual one unless some rule needs its own.
+    pub fn line_starts(&self) -> &[usize] {
+        if self.dfa.starts().len() > self.mode_count {
+            &self.dfa.starts()[self.mode_count..]
+        } else {
+            self.starts()
+        }
+    }
+
+    pub fn next(&self, state_id: usize, c: char) -> Option<usize> {
+        self.dfa.next(state_id, c)
+    }
+
+    /// The index of the rule that wins `state_id`, if it is accepting.
+    pub fn token_of(&self, state_id: usize) -> Option<usize> {
+        self.dfa.token_of(state_id)
+    }
+
+    /// The name of the token that wins `state_id`, if it is accepting.
+    pub fn token_name_of(&self, state_id: usize) -> Option<&str> {
+        self.token_of(state_id)
+            .map(|token| self.tokens[token].as_str())
+    }
+
+    /// The token names, in rule order.
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// The trailing context of `rule`, if it has one.
+    pub fn context_of(&self, rule: usize) -> Option<TrailingContext> {
+        self.contexts[rule]
+    }
+
+    /// The rule to run when the input runs out in `mode`, if any.
+    pub fn end_rule(&self, mode: usize) -> Option<usize> {
+        self.end_rules[mode]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex::{Definitions, Span};
+
+    fn run<'a>(dfa: &'a LexerDfa, input: &str) -> Option<&'a str> {
+        run_in(dfa, 0, input)
+    }
+
+    fn run_in<'a>(dfa: &'a LexerDfa, mode: usize, input: &str) -> Option<&'a str> {
+        let mut state = dfa.starts()[mode];
+
+        for c in input.chars() {
+            state = dfa.next(state, c)?;
+        }
+
+        dfa.token_name_of(state)
+    }
+
+    fn lexer(rules: &[(&str, &str)]) -> LexerDfa {
+        LexerDfa::from_nfa(&LexerNfa::from_rules(rules).unwrap())
+    }
+
+    #[test]
+    fn rules_share_a_start_state() {
+        let dfa = lexer(&[("Number", "0-9+"), ("Ident", "a-z+"), ("Plus", r#" "+" "#)]);
+
+        assert_eq!(run(&dfa, "42"), Some("Number"));
+        assert_eq!(run(&dfa, "abc"), Some("Ident"));
+        assert_eq!(run(&dfa, "+"), Some("Plus"));
+        assert_eq!(run(&dfa, "a1"), None);
+        assert_eq!(run(&dfa, ""), None);
+    }
+
+    #[test]
+    fn earliest_rule_wins() {
+        let dfa = lexer(&[("If", r#" "if" "#), ("Ident", "a-z+")]);
+
+        assert_eq!(run(&dfa, "if"), Some("If"));
+        assert_eq!(run(&dfa, "iff"), Some("Ident"));
+        assert_eq!(run(&dfa, "i"), Some("Ident"));
+
+        let dfa = lexer(&[("Ident", "a-z+"), ("If", r#" "if" "#)]);
+
+        assert_eq!(run(&dfa, "if"), Some("Ident"));
+    }
+
+    #[test]
+    fn minimize_keeps_tokens_apart() {
+        let mut dfa = lexer(&[("If", r#" "if" "#), ("In", r#" "in" "#), ("Ident", "a-z+")]);
+
+        dfa.minimize();
+
+        assert_eq!(run(&dfa, "if"), Some("If"));
+        assert_eq!(run(&dfa, "in"), Some("In"));
+        assert_eq!(run(&dfa, "ix"), Some("Ident"));
+        assert_eq!(run(&dfa, "inx"), Some("Ident"));
+    }
+
+    #[test]
+    fn token_of_finish_states() {
+        let nfa = LexerNfa::from_rules(&[("A", "a"), ("B", "b")]).unwrap();
+
+        let mut tokens: Vec<usize> = (0..10).filter_map(|state| nfa.token_of(state)).collect();
+        tokens.sort_unstable();
+
+        assert_eq!(tokens, vec![0, 1]);
+        assert_eq!(nfa.tokens(), &["A".to_string(), "B".to_string()]);
+        assert_eq!(nfa.token_of(nfa.sid()), None);
+    }
+
+    #[test]
+    fn rules_with_definitions() {
+        let mut definitions = Definitions::new();
+        definitions.define("digit", "0-9").unwrap();
+
+        let nfa = LexerNfa::from_rules_with(&[("Number", "digit+")], &definitions).unwrap();
+        let dfa = LexerDfa::from_nfa(&nfa);
+
+        assert_eq!(run(&dfa, "123"), Some("Number"));
+        assert_eq!(run(&dfa, "digit"), None);
+    }
+
+    #[test]
+    fn start_state_per_mode() {
+        let rules = [
+            ("Quote", r#" "\"" "#),
+            ("Ident", "a-z+"),
+            ("Text", r#" [^"]+ "#),
+            ("Unquote", r#" "\"" "#),
+        ];
+        let modes = [vec![0, 1], vec![0], vec![1], vec![1]];
+
+        let nfa = LexerNfa::from_rules_in_modes(&rules, &modes, 3, &Definitions::new()).unwrap();
+        let mut dfa = LexerDfa::from_nfa(&nfa);
+
+        assert_eq!(nfa.starts().len(), 3);
+
+        for minimized in &[false, true] {
+            if *minimized {
+                dfa.minimize();
+            }
+
+            assert_eq!(dfa.starts().len(), 3);
+            assert_eq!(dfa.sid(), dfa.starts()[0]);
+            assert_eq!(run_in(&dfa, 0, "abc"), Some("Ident"));
+            assert_eq!(run_in(&dfa, 1, "abc"), Some("Text"));
+            assert_eq!(run_in(&dfa, 0, "\""), Some("Quote"));
+            assert_eq!(run_in(&dfa, 1, "\""), Some("Quote"));
+            assert_eq!(run_in(&dfa, 0, "a b"), None);
+            assert_eq!(run_in(&dfa, 1, "a b"), Some("Text"));
+            assert_eq!(run_in(&dfa, 2, "a"), None);
+        }
+    }
+
+    #[test]
+    fn error_names_the_rule() {
+        let error = LexerNfa::from_rules(&[("A", "a"), ("B", "b |")])
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            error,
+            RuleError {
+                rule: 1,
+                error: RegexError::DanglingOperator(Span::new(2, 3))
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "rule 1: operator is missing its right operand"
+        );
+    }
+
+    #[test]
+    fn token_len_drops_the_context() {
+        assert_eq!(TrailingContext::Head(2).token_len("ifé("), 2);
+        assert_eq!(TrailingContext::Head(2).token_len("é("), 3);
+        assert_eq!(TrailingContext::Tail(2).token_len("12é."), 2);
+        assert_eq!(TrailingContext::Tail(0).token_len("12"), 2);
+        assert_eq!(TrailingContext::Tail(3).token_len("12"), 0);
+    }
+
+    #[test]
+    fn start_state_at_line_start() {
+        let rules = [("Hash", r##" ^ "#" "##), ("Ident", "a-z+")];
+        let modes = [vec![1], vec![0, 1]];
+
+        let nfa = LexerNfa::from_rules_in_modes(&rules, &modes, 2, &Definitions::new()).unwrap();
+        let mut dfa = LexerDfa::from_nfa(&nfa);
+
+        assert_eq!(nfa.line_starts().len(), 2);
+
+        for minimized in &[false, true] {
+            if *minimized {
+                dfa.minimize();
+            }
+
+            assert_eq!(dfa.starts().len(), 2);
+            assert_eq!(dfa.line_starts().len(), 2);
+
+            let hash = |start: usize| dfa.next(start, '#').and_then(|state| dfa.token_of(state));
+
+            assert_eq!(hash(dfa.starts()[1]), None);
+            assert_eq!(hash(dfa.line_starts()[1]), Some(0));
+            assert_eq!(hash(dfa.line_starts()[0]), None);
+            assert!(dfa.next(dfa.line_starts()[0], 'a').is_some());
+        }
+    }
+}
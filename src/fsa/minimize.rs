@@ -0,0 +1,321 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use super::Dfa;
+use crate::regex::next_char;
+
+/// Splits the chars into disjoint ranges such that every transition of `dfa`
+/// covers either all or none of the chars of a range. Returns the first char
+/// of each range, sorted.
+fn symbols_of(dfa: &Dfa) -> Vec<char> {
+    let mut bounds = BTreeSet::new();
+
+    for state in 0..dfa.state_count() {
+        for &(from, to, _) in dfa.transitions(state) {
+            bounds.insert(from);
+            bounds.extend(next_char(to));
+        }
+    }
+
+    bounds.into_iter().collect()
+}
+
+impl Dfa {
+    /// Merges equivalent states with Hopcroft's partition refinement and
+    /// returns the number of states that were removed.
+    ///
+    /// States that accept different tokens are never merged. States from
+    /// which no accepting state can be reached are dropped along with the
+    /// transitions into them, except for the start state.
+    pub fn minimize(&mut self) -> usize {
+        let count = self.state_count();
+        let symbols = symbols_of(self);
+
+        // Missing transitions lead to an extra dead state, so that every
+        // state has a successor on every symbol.
+        let dead = count;
+
+        let mut inverse: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+
+        for state in 0..=count {
+            let mut targets = vec![dead; symbols.len()];
+
+            if state != dead {
+                for &(from, to, target) in self.transitions(state) {
+                    let first = symbols.binary_search(&from).unwrap();
+
+                    for symbol in first..symbols.len() {
+                        if symbols[symbol] > to {
+                            break;
+                        }
+
+                        targets[symbol] = target;
+                    }
+                }
+            }
+
+            for (symbol, target) in targets.into_iter().enumerate() {
+                inverse.entry((symbol, target)).or_default().push(state);
+            }
+        }
+
+        // Start with one block per accepted token, plus one for the states
+        // that do not accept.
+        let mut groups: BTreeMap<Option<usize>, Vec<usize>> = BTreeMap::new();
+
+        for state in 0..=count {
+            let token = if state == dead {
+                None
+            } else {
+                self.token_of(state)
+            };
+
+            groups.entry(token).or_default().push(state);
+        }
+
+        let mut blocks: Vec<Vec<usize>> = groups.into_values().collect();
+        let mut block_of = vec![0; count + 1];
+
+        for (block, states) in blocks.iter().enumerate() {
+            for &state in states {
+                block_of[state] = block;
+            }
+        }
+
+        let largest = (0..blocks.len())
+            .max_by_key(|&block| blocks[block].len())
+            .unwrap();
+
+        let mut worklist: Vec<usize> = (0..blocks.len()).filter(|&b| b != largest).collect();
+        let mut in_worklist: Vec<bool> = (0..blocks.len()).map(|b| b != largest).collect();
+
+        while let Some(splitter) = worklist.pop() {
+            in_worklist[splitter] = false;
+
+            let members = blocks[splitter].clone();
+
+            for symbol in 0..symbols.len() {
+                // The states that move into the splitter on `symbol`, grouped
+                // by their block.
+                let mut touched: HashMap<usize, HashSet<usize>> = HashMap::new();
+
+                for target in &members {
+                    for &source in inverse.get(&(symbol, *target)).into_iter().flatten() {
+                        touched.entry(block_of[source]).or_default().insert(source);
+                    }
+                }
+
+                for (block, inside) in touched {
+                    if inside.len() == blocks[block].len() {
+                        continue;
+                    }
+
+                    let (inside, outside): (Vec<usize>, Vec<usize>) = blocks[block]
+                        .iter()
+                        .partition(|state| inside.contains(state));
+
+                    let split = blocks.len();
+
+                    for &state in &inside {
+                        block_of[state] = split;
+                    }
+
+                    blocks[block] = outside;
+                    blocks.push(inside);
+
+                    // Refining by the smaller half is enough, unless the
+                    // block was still waiting to be used as a splitter.
+                    if in_worklist[block] || blocks[split].len() <= blocks[block].len() {
+                        worklist.push(split);
+                        in_worklist.push(true);
+                    } else {
+                        worklist.push(block);
+                        in_worklist[block] = true;
+                        in_worklist.push(false);
+                    }
+                }
+            }
+        }
+
+        // Renumber the blocks in the order they are reached from the starts,
+        // leaving out the block of the dead state.
+        let dead_block = block_of[dead];
+
+        let mut ids = HashMap::new();
+        let mut order = vec![];
+        let mut transitions = vec![];
+        let mut accepting = HashMap::new();
+
+        let starts = self
+            .starts()
+            .iter()
+            .map(|&start| {
+                *ids.entry(block_of[start]).or_insert_with(|| {
+                    order.push(block_of[start]);
+                    order.len() - 1
+                })
+            })
+            .collect();
+
+        while transitions.len() < order.len() {
+            let id = transitions.len();
+            let block = order[id];
+            let mut row: Vec<(char, char, usize)> = vec![];
+
+            if block == dead_block {
+                transitions.push(row);
+                continue;
+            }
+
+            // Every state of a block moves into the same blocks, so any of
+            // them can stand for the whole block.
+            let representative = blocks[block][0];
+
+            if let Some(token) = self.token_of(representative) {
+                accepting.insert(id, token);
+            }
+
+            for &(from, to, target) in self.transitions(representative) {
+                let target_block = block_of[target];
+
+                if target_block == dead_block {
+                    continue;
+                }
+
+                let target = *ids.entry(target_block).or_insert_with(|| {
+                    order.push(target_block);
+                    order.len() - 1
+                });
+
+                match row.last_mut() {
+                    Some((_, last_to, last_target))
+                        if *last_target == target && next_char(*last_to) == Some(from) =>
+                    {
+                        *last_to = to
+                    }
+                    _ => row.push((from, to, target)),
+                }
+            }
+
+            transitions.push(row);
+        }
+
+        *self = Dfa::from_parts(starts, accepting, transitions);
+
+        count - self.state_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fsa::Nfa;
+
+    fn dfa_of(regex: &str) -> Dfa {
+        Dfa::from_nfa(&Nfa::from_regex(regex).unwrap())
+    }
+
+    fn accepts(dfa: &Dfa, input: &str) -> bool {
+        let mut state = dfa.sid();
+
+        for c in input.chars() {
+            match dfa.next(state, c) {
+                Some(next) => state = next,
+                None => return false,
+            }
+        }
+
+        dfa.is_accepting(state)
+    }
+
+    #[test]
+    fn textbook_example() {
+        let mut dfa = dfa_of(r#" (a | b)* a b b "#);
+        let before = dfa.state_count();
+
+        let removed = dfa.minimize();
+
+        assert_eq!(dfa.state_count(), 4);
+        assert_eq!(removed, before - 4);
+        assert!(accepts(&dfa, "abb"));
+        assert!(accepts(&dfa, "babaabb"));
+        assert!(!accepts(&dfa, "abba"));
+        assert!(!accepts(&dfa, ""));
+    }
+
+    #[test]
+    fn keeps_the_language() {
+        let regex = r#" "0x" [0-9a-f]+ | (a | b) "x" 0-9+ ("." 0-9+)? "#;
+        let original = dfa_of(regex);
+        let mut minimized = original.clone();
+
+        minimized.minimize();
+
+        assert!(minimized.state_count() < original.state_count());
+
+        for input in ["0x1f", "0x", "ax12", "bx1.5", "ax1.", "bx.5", "0xg", ""] {
+            assert_eq!(accepts(&minimized, input), accepts(&original, input));
+        }
+    }
+
+    #[test]
+    fn minimal_dfa_is_unchanged() {
+        let mut dfa = dfa_of(r#" a-z+ "#);
+
+        dfa.minimize();
+
+        let minimal = dfa.clone();
+
+        assert_eq!(dfa.minimize(), 0);
+        assert_eq!(dfa, minimal);
+        assert_eq!(dfa.state_count(), 2);
+    }
+
+    #[test]
+    fn distinct_tokens_are_not_merged() {
+        // Two otherwise equivalent accepting states, one for each token.
+        let accepting = vec![(1, 0), (2, 1)].into_iter().collect();
+        let transitions = vec![vec![('a', 'a', 1), ('b', 'b', 2)], vec![], vec![]];
+
+        let mut dfa = Dfa::from_parts(vec![0], accepting, transitions);
+
+        assert_eq!(dfa.minimize(), 0);
+        assert_eq!(dfa.token_of(dfa.next(0, 'a').unwrap()), Some(0));
+        assert_eq!(dfa.token_of(dfa.next(0, 'b').unwrap()), Some(1));
+
+        let accepting = vec![(1, 0), (2, 0)].into_iter().collect();
+        let transitions = vec![vec![('a', 'a', 1), ('b', 'b', 2)], vec![], vec![]];
+
+        let mut dfa = Dfa::from_parts(vec![0], accepting, transitions);
+
+        assert_eq!(dfa.minimize(), 1);
+        assert_eq!(dfa.transitions(0), &[('a', 'b', 1)]);
+    }
+
+    #[test]
+    fn dead_states_are_removed() {
+        // State 2 can never reach the accepting state 1.
+        let accepting = vec![(1, 0)].into_iter().collect();
+        let transitions = vec![
+            vec![('a', 'a', 1), ('b', 'b', 2)],
+            vec![],
+            vec![('c', 'c', 2)],
+        ];
+
+        let mut dfa = Dfa::from_parts(vec![0], accepting, transitions);
+
+        assert_eq!(dfa.minimize(), 1);
+        assert_eq!(dfa.transitions(0), &[('a', 'a', 1)]);
+    }
+
+    #[test]
+    fn empty_language() {
+        let accepting = HashMap::new();
+        let transitions = vec![vec![('a', 'a', 1)], vec![]];
+
+        let mut dfa = Dfa::from_parts(vec![0], accepting, transitions);
+
+        assert_eq!(dfa.minimize(), 1);
+        assert_eq!(dfa.state_count(), 1);
+        assert!(dfa.transitions(0).is_empty());
+    }
+}
@@ -1,3 +1,12 @@
+mod compile;
+mod dfa;
+mod dot;
+mod lexer;
+mod minimize;
 mod nfa;
+mod simulate;
 
-pub use nfa::Nfa;
+pub use compile::REPEAT_LIMIT;
+pub use dfa::Dfa;
+pub use lexer::{LexerDfa, LexerNfa, RuleError, TrailingContext};
+pub use nfa::{Lable, Nfa};
@@ -3,6 +3,8 @@ use std::collections::{
     HashMap,
 };
 
+use crate::regex::CharClass;
+
 pub fn utoc(digit: usize) -> char {
     match digit {
         0 => '0',
@@ -19,12 +21,24 @@ pub fn utoc(digit: usize) -> char {
     }
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Lable {
     Char(char),
+    Class(CharClass),
     Eps,
 }
 
+impl Lable {
+    /// Whether an edge with this lable can be taken on `c`.
+    pub fn matches(&self, c: char) -> bool {
+        match self {
+            Lable::Char(lable) => *lable == c,
+            Lable::Class(class) => class.contains(c),
+            Lable::Eps => false,
+        }
+    }
+}
+
 impl From<char> for Lable {
     fn from(c: char) -> Self {
         Lable::Char(c)
@@ -36,24 +50,38 @@ pub struct Nfa {
     finish_id: usize,
 
     delta: HashMap<(usize, Lable), Vec<usize>>,
+
+    // The class transitions of `delta` again, grouped by source state, so
+    // `targets` does not have to scan the whole delta to find the classes
+    // containing a char.
+    classes: HashMap<usize, Vec<(CharClass, Vec<usize>)>>,
 }
 
 impl Nfa {
-    pub fn of_text(text: &str, offset: usize) -> Self {
-        let start_id = offset;
-        let finish_id = offset + text.len();
-        let delta = HashMap::new();
+    pub(super) fn with_ids(start_id: usize, finish_id: usize) -> Self {
+        Nfa {
+            start_id,
+            finish_id,
+            delta: HashMap::new(),
+            classes: HashMap::new(),
+        }
+    }
 
+    /// Moves the transitions of `other` into `self`.
+    pub(super) fn absorb(&mut self, other: Nfa) {
+        self.delta.extend(other.delta);
+        self.classes.extend(other.classes);
+    }
+
+    pub fn of_text(text: &str, offset: usize) -> Self {
         let chars: Vec<char> = text.chars().collect();
 
-        let mut nfa = Nfa {
-            start_id,
-            finish_id,
-            delta,
-        };
+        let start_id = offset;
+        let finish_id = offset + chars.len();
+        let mut nfa = Nfa::with_ids(start_id, finish_id);
 
-        for index in 0..chars.len() {
-            nfa.insert_transition(index + offset, chars[index].into(), index + offset + 1);
+        for (index, c) in chars.iter().enumerate() {
+            nfa.insert_transition(index + offset, (*c).into(), index + offset + 1);
         }
 
         nfa
@@ -62,13 +90,7 @@ impl Nfa {
     pub fn of_digit(digit: usize, offset: usize) -> Self {
         let start_id = offset;
         let finish_id = offset + 1;
-        let delta = HashMap::new();
-
-        let mut nfa = Nfa {
-            start_id,
-            finish_id,
-            delta,
-        };
+        let mut nfa = Nfa::with_ids(start_id, finish_id);
 
         nfa.insert_transition(start_id, utoc(digit).into(), finish_id);
 
@@ -78,13 +100,7 @@ impl Nfa {
     pub fn of_char(c: char, offset: usize) -> Self {
         let start_id = offset;
         let finish_id = offset + 1;
-        let delta = HashMap::new();
-
-        let mut nfa = Nfa {
-            start_id,
-            finish_id,
-            delta,
-        };
+        let mut nfa = Nfa::with_ids(start_id, finish_id);
 
         nfa.insert_transition(start_id, c.into(), finish_id);
 
@@ -94,35 +110,25 @@ impl Nfa {
     pub fn of_eps(offset: usize) -> Self {
         let start_id = offset;
         let finish_id = offset + 1;
-
-        let delta = HashMap::new();
-
-        let mut nfa = Nfa {
-            start_id,
-            finish_id,
-            delta,
-        };
+        let mut nfa = Nfa::with_ids(start_id, finish_id);
 
         nfa.insert_transition(start_id, Lable::Eps, finish_id);
 
         nfa
     }
 
+    /// Builds an Nfa matching any char in `c1..=c2` with a single class
+    /// transition, however large the range is.
     pub fn of_cdash(c1: char, c2: char, offset: usize) -> Self {
+        Nfa::of_class(CharClass::of_range(c1, c2), offset)
+    }
+
+    pub fn of_class(class: CharClass, offset: usize) -> Self {
         let start_id = offset;
         let finish_id = offset + 1;
+        let mut nfa = Nfa::with_ids(start_id, finish_id);
 
-        let delta = HashMap::new();
-
-        let mut nfa = Nfa {
-            start_id,
-            finish_id,
-            delta,
-        };
-
-        for c in c1..=c2 {
-            nfa.insert_transition(start_id, c.into(), finish_id);
-        }
+        nfa.insert_transition(start_id, Lable::Class(class), finish_id);
 
         nfa
     }
@@ -140,19 +146,14 @@ impl Nfa {
 
         let nfa1_sid = nfa1.sid();
         let nfa1_fid = nfa1.fid();
-        let mut delta = nfa1.get_delta();
 
         let nfa2_sid = nfa2.sid();
         let nfa2_fid = nfa2.fid();
-        let delta2 = nfa2.get_delta();
 
-        delta.extend(delta2);
+        let mut nfa = Nfa::with_ids(start_id, finish_id);
 
-        let mut nfa = Nfa {
-            start_id,
-            finish_id,
-            delta,
-        };
+        nfa.absorb(nfa1);
+        nfa.absorb(nfa2);
 
         nfa.insert_transition(start_id, Lable::Eps, nfa1_sid);
         nfa.insert_transition(start_id, Lable::Eps, nfa2_sid);
@@ -169,16 +170,10 @@ impl Nfa {
         let nfa1_fid = nfa1.fid();
         let nfa2_sid = nfa2.sid();
 
-        let mut delta = nfa1.get_delta();
-        let delta2 = nfa2.get_delta();
-
-        delta.extend(delta2);
+        let mut nfa = Nfa::with_ids(start_id, finish_id);
 
-        let mut nfa = Nfa {
-            start_id,
-            finish_id,
-            delta,
-        };
+        nfa.absorb(nfa1);
+        nfa.absorb(nfa2);
 
         nfa.insert_transition(nfa1_fid, Lable::Eps, nfa2_sid);
 
@@ -192,13 +187,10 @@ impl Nfa {
         let nfa_sid = nfa.sid();
         let nfa_fid = nfa.fid();
 
-        let delta = nfa.get_delta();
+        let inner = nfa;
+        let mut nfa = Nfa::with_ids(start_id, finish_id);
 
-        let mut nfa = Nfa {
-            start_id,
-            finish_id,
-            delta,
-        };
+        nfa.absorb(inner);
 
         nfa.insert_transition(nfa_fid, Lable::Eps, nfa_sid);
         nfa.insert_transition(start_id, Lable::Eps, nfa_sid);
@@ -215,13 +207,10 @@ impl Nfa {
         let nfa_sid = nfa.sid();
         let nfa_fid = nfa.fid();
 
-        let delta = nfa.get_delta();
+        let inner = nfa;
+        let mut nfa = Nfa::with_ids(start_id, finish_id);
 
-        let mut nfa = Nfa {
-            start_id,
-            finish_id,
-            delta,
-        };
+        nfa.absorb(inner);
 
         nfa.insert_transition(nfa_fid, Lable::Eps, nfa_sid);
         nfa.insert_transition(start_id, Lable::Eps, nfa_sid);
@@ -237,13 +226,10 @@ impl Nfa {
         let nfa_sid = nfa.sid();
         let nfa_fid = nfa.fid();
 
-        let delta = nfa.get_delta();
+        let inner = nfa;
+        let mut nfa = Nfa::with_ids(start_id, finish_id);
 
-        let mut nfa = Nfa {
-            start_id,
-            finish_id,
-            delta,
-        };
+        nfa.absorb(inner);
 
         nfa.insert_transition(start_id, Lable::Eps, nfa_sid);
         nfa.insert_transition(start_id, Lable::Eps, finish_id);
@@ -253,6 +239,15 @@ impl Nfa {
     }
 
     pub fn insert_transition(&mut self, src_id: usize, lable: Lable, dst_id: usize) {
+        if let Lable::Class(class) = &lable {
+            let classes = self.classes.entry(src_id).or_default();
+
+            match classes.iter_mut().find(|(other, _)| other == class) {
+                Some((_, targets)) => targets.push(dst_id),
+                None => classes.push((class.clone(), vec![dst_id])),
+            }
+        }
+
         match self.delta.entry((src_id, lable)) {
             Occupied(mut occ) => {
                 occ.get_mut().push(dst_id);
@@ -278,6 +273,33 @@ impl Nfa {
     pub fn delta(&self, (state_id, lable): (usize, Lable)) -> Option<&Vec<usize>> {
         self.delta.get(&(state_id, lable))
     }
+
+    /// States reachable from `state_id` by consuming `c`.
+    ///
+    /// Char lables are found by a single lookup and class lables through the
+    /// classes leaving `state_id`, each searched in logarithmic time.
+    pub fn targets(&self, state_id: usize, c: char) -> Vec<usize> {
+        let mut targets = self
+            .delta
+            .get(&(state_id, Lable::Char(c)))
+            .cloned()
+            .unwrap_or_default();
+
+        if let Some(classes) = self.classes.get(&state_id) {
+            for (_, class_targets) in classes.iter().filter(|(class, _)| class.contains(c)) {
+                targets.extend(class_targets);
+            }
+        }
+
+        targets
+    }
+
+    /// Every transition of the automaton, as source state, lable and targets.
+    pub fn transitions(&self) -> impl Iterator<Item = (usize, &Lable, &Vec<usize>)> {
+        self.delta
+            .iter()
+            .map(|((state_id, lable), targets)| (*state_id, lable, targets))
+    }
 }
 
 #[cfg(test)]
@@ -306,6 +328,18 @@ mod tests {
         assert_eq!(nfa.fid(), 8);
     }
 
+    #[test]
+    fn of_text_with_multi_byte_chars() {
+        let nfa = Nfa::of_text("سلام", 5);
+
+        assert_eq!(nfa.sid(), 5);
+        assert_eq!(nfa.delta((5, 'س'.into())).unwrap()[0], 6);
+        assert_eq!(nfa.delta((6, 'ل'.into())).unwrap()[0], 7);
+        assert_eq!(nfa.delta((7, 'ا'.into())).unwrap()[0], 8);
+        assert_eq!(nfa.delta((8, 'م'.into())).unwrap()[0], 9);
+        assert_eq!(nfa.fid(), 9);
+    }
+
     #[test]
     fn of_digit() {
         let nfa = Nfa::of_digit(1, 0);
@@ -365,9 +399,9 @@ mod tests {
         let nfa = Nfa::of_cdash('a', 'c', 0);
 
         assert_eq!(nfa.sid(), 0);
-        assert_eq!(nfa.delta((0, 'a'.into())).unwrap()[0], 1);
-        assert_eq!(nfa.delta((0, 'b'.into())).unwrap()[0], 1);
-        assert_eq!(nfa.delta((0, 'c'.into())).unwrap()[0], 1);
+        assert_eq!(nfa.targets(0, 'a'), vec![1]);
+        assert_eq!(nfa.targets(0, 'b'), vec![1]);
+        assert_eq!(nfa.targets(0, 'c'), vec![1]);
         assert_eq!(nfa.fid(), 1);
     }
 
@@ -376,20 +410,86 @@ mod tests {
         let nfa = Nfa::of_cdash('a', 'c', 5);
 
         assert_eq!(nfa.sid(), 5);
-        assert_eq!(nfa.delta((5, 'a'.into())).unwrap()[0], 6);
-        assert_eq!(nfa.delta((5, 'b'.into())).unwrap()[0], 6);
-        assert_eq!(nfa.delta((5, 'c'.into())).unwrap()[0], 6);
+        assert_eq!(nfa.targets(5, 'a'), vec![6]);
+        assert_eq!(nfa.targets(5, 'b'), vec![6]);
+        assert_eq!(nfa.targets(5, 'c'), vec![6]);
         assert_eq!(nfa.fid(), 6);
     }
 
+    #[test]
+    fn of_cdash_is_a_single_transition() {
+        let nfa = Nfa::of_cdash('\u{0}', '\u{10FFFF}', 0);
+
+        assert_eq!(nfa.transitions().count(), 1);
+        assert_eq!(
+            nfa.delta((0, Lable::Class(CharClass::of_range('\u{0}', '\u{10FFFF}'))))
+                .unwrap()[0],
+            1
+        );
+        assert_eq!(nfa.targets(0, '\u{10FFFF}'), vec![1]);
+        assert_eq!(nfa.targets(0, 'ß'), vec![1]);
+    }
+
+    #[test]
+    fn targets() {
+        let mut nfa = Nfa::of_cdash('a', 'z', 0);
+        nfa.insert_transition(0, 'k'.into(), 2);
+        nfa.insert_transition(0, Lable::Class(CharClass::of_range('j', 'l')), 3);
+        nfa.insert_transition(0, Lable::Class(CharClass::of_range('j', 'l')), 4);
+        nfa.insert_transition(0, Lable::Eps, 5);
+
+        assert_eq!(nfa.targets(0, 'a'), vec![1]);
+        assert_eq!(nfa.targets(0, 'j'), vec![1, 3, 4]);
+
+        let mut targets = nfa.targets(0, 'k');
+        targets.sort_unstable();
+
+        assert_eq!(targets, vec![1, 2, 3, 4]);
+        assert!(nfa.targets(0, 'A').is_empty());
+        assert!(nfa.targets(1, 'a').is_empty());
+    }
+
+    #[test]
+    fn targets_after_combining() {
+        let a_to_c = Nfa::of_cdash('a', 'c', 0);
+        let x_to_z = Nfa::of_cdash('x', 'z', 2);
+        let nfa = Nfa::of_or(a_to_c, x_to_z, 4);
+
+        assert_eq!(nfa.targets(0, 'b'), vec![1]);
+        assert_eq!(nfa.targets(2, 'y'), vec![3]);
+        assert!(nfa.targets(0, 'y').is_empty());
+    }
+
+    #[test]
+    fn of_class() {
+        let class = CharClass::parse("a-cx", 0).unwrap();
+        let nfa = Nfa::of_class(class.clone(), 5);
+
+        assert_eq!(nfa.sid(), 5);
+        assert_eq!(nfa.delta((5, Lable::Class(class))).unwrap()[0], 6);
+        assert_eq!(nfa.transitions().count(), 1);
+        assert_eq!(nfa.fid(), 6);
+    }
+
+    #[test]
+    fn lable_matches() {
+        let class = Lable::Class(CharClass::parse("^a-c", 0).unwrap());
+
+        assert!(Lable::Char('a').matches('a'));
+        assert!(!Lable::Char('a').matches('b'));
+        assert!(class.matches('d'));
+        assert!(!class.matches('b'));
+        assert!(!Lable::Eps.matches('a'));
+    }
+
     #[test]
     fn of_ndash() {
         let nfa = Nfa::of_ndash(2, 4, 0);
 
         assert_eq!(nfa.sid(), 0);
-        assert_eq!(nfa.delta((0, '2'.into())).unwrap()[0], 1);
-        assert_eq!(nfa.delta((0, '3'.into())).unwrap()[0], 1);
-        assert_eq!(nfa.delta((0, '4'.into())).unwrap()[0], 1);
+        assert_eq!(nfa.targets(0, '2'), vec![1]);
+        assert_eq!(nfa.targets(0, '3'), vec![1]);
+        assert_eq!(nfa.targets(0, '4'), vec![1]);
         assert_eq!(nfa.fid(), 1);
     }
 
@@ -398,9 +498,9 @@ mod tests {
         let nfa = Nfa::of_ndash(2, 4, 5);
 
         assert_eq!(nfa.sid(), 5);
-        assert_eq!(nfa.delta((5, '2'.into())).unwrap()[0], 6);
-        assert_eq!(nfa.delta((5, '3'.into())).unwrap()[0], 6);
-        assert_eq!(nfa.delta((5, '4'.into())).unwrap()[0], 6);
+        assert_eq!(nfa.targets(5, '2'), vec![6]);
+        assert_eq!(nfa.targets(5, '3'), vec![6]);
+        assert_eq!(nfa.targets(5, '4'), vec![6]);
         assert_eq!(nfa.fid(), 6);
     }
 
@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+
+use super::{Lable, Nfa};
+
+impl Nfa {
+    /// The states reachable from `states` by epsilon transitions alone,
+    /// sorted.
+    pub fn closure<I>(&self, states: I) -> Vec<usize>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let mut closure = HashSet::new();
+        let mut stack = vec![];
+
+        for state in states {
+            if closure.insert(state) {
+                stack.push(state);
+            }
+        }
+
+        while let Some(state) = stack.pop() {
+            if let Some(targets) = self.delta((state, Lable::Eps)) {
+                for &target in targets {
+                    if closure.insert(target) {
+                        stack.push(target);
+                    }
+                }
+            }
+        }
+
+        let mut closure: Vec<usize> = closure.into_iter().collect();
+        closure.sort_unstable();
+
+        closure
+    }
+
+    /// The states reachable from `states` by consuming `c`, followed by any
+    /// number of epsilon transitions.
+    fn step(&self, states: &[usize], c: char) -> Vec<usize> {
+        let moved: Vec<usize> = states
+            .iter()
+            .flat_map(|state| self.targets(*state, c))
+            .collect();
+
+        self.closure(moved)
+    }
+
+    fn is_finish(&self, states: &[usize]) -> bool {
+        states.binary_search(&self.fid()).is_ok()
+    }
+
+    /// Whether the automaton accepts the whole of `input`.
+    ///
+    /// The automaton is simulated directly, tracking the set of states it
+    /// can be in after each char.
+    pub fn is_match(&self, input: &str) -> bool {
+        let mut states = self.closure(vec![self.sid()]);
+
+        for c in input.chars() {
+            states = self.step(&states, c);
+
+            if states.is_empty() {
+                return false;
+            }
+        }
+
+        self.is_finish(&states)
+    }
+
+    /// The length in bytes of the longest prefix of `input` the automaton
+    /// accepts, or `None` if it accepts no prefix, not even the empty one.
+    pub fn longest_prefix_match(&self, input: &str) -> Option<usize> {
+        let mut states = self.closure(vec![self.sid()]);
+        let mut longest = if self.is_finish(&states) {
+            Some(0)
+        } else {
+            None
+        };
+
+        for (index, c) in input.char_indices() {
+            states = self.step(&states, c);
+
+            if states.is_empty() {
+                break;
+            }
+
+            if self.is_finish(&states) {
+                longest = Some(index + c.len_utf8());
+            }
+        }
+
+        longest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combinators() {
+        let a = Nfa::of_char('a', 0);
+        let b = Nfa::of_char('b', 2);
+        let a_or_b = Nfa::of_or(a, b, 4);
+        let star = Nfa::of_star(a_or_b, 6);
+        let c = Nfa::of_char('c', 8);
+        let nfa = Nfa::of_concat(star, c, 0);
+
+        assert!(nfa.is_match("c"));
+        assert!(nfa.is_match("abbac"));
+        assert!(!nfa.is_match("abba"));
+        assert!(!nfa.is_match("cc"));
+    }
+
+    #[test]
+    fn is_match_of_regex() {
+        let nfa = Nfa::from_regex(r#" "0x" [0-9a-f]+ "#).unwrap();
+
+        assert!(nfa.is_match("0xff"));
+        assert!(!nfa.is_match("0x"));
+        assert!(!nfa.is_match("0xfg"));
+    }
+
+    #[test]
+    fn longest_prefix_match() {
+        let nfa = Nfa::from_regex(r#" 0-9+ ("." 0-9+)? "#).unwrap();
+
+        assert_eq!(nfa.longest_prefix_match("3.14 rest"), Some(4));
+        assert_eq!(nfa.longest_prefix_match("3.x"), Some(1));
+        assert_eq!(nfa.longest_prefix_match("x3"), None);
+        assert_eq!(nfa.longest_prefix_match(""), None);
+    }
+
+    #[test]
+    fn longest_prefix_match_can_be_empty() {
+        let nfa = Nfa::from_regex(r#" a* "#).unwrap();
+
+        assert_eq!(nfa.longest_prefix_match("b"), Some(0));
+        assert_eq!(nfa.longest_prefix_match("aab"), Some(2));
+    }
+
+    #[test]
+    fn longest_prefix_match_counts_bytes() {
+        let nfa = Nfa::from_regex(r#" α-ω+ "#).unwrap();
+
+        assert_eq!(nfa.longest_prefix_match("λμ!"), Some(4));
+    }
+
+    #[test]
+    fn closure_follows_eps_chains() {
+        let nfa = Nfa::of_question(Nfa::of_eps(0), 2);
+
+        assert_eq!(nfa.closure(vec![2]), vec![0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,422 @@
+use crate::fsa::LexerDfa;
+use crate::regex::Span;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// A match of the rule with this index.
+    Rule(usize),
+    /// A char at which no rule matches.
+    Error,
+}
+
+/// A piece of the input, with the byte offsets it was found at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+impl<'a> Token<'a> {
+    pub fn is_error(&self) -> bool {
+        self.kind == TokenKind::Error
+    }
+}
+
+/// Splits an input into the tokens of a `LexerDfa`.
+///
+/// Every token is the longest prefix of the remaining input that some rule
+/// matches, won by the earliest of those rules. When no rule matches a
+/// non-empty prefix, the next char is returned as an error token and lexing
+/// goes on after it. Rules are never matched against the empty string.
+///
+/// The trailing context of a rule takes part in finding the longest match,
+/// but is not part of the token, and is lexed again after it.
+///
+/// Only the rules of the current mode are matched. The modes form a stack,
+/// whose bottom is mode `0`.
+///
+/// Rules starting with `^` only match at the start of the input or after a
+/// '\n'. When the input runs out in a mode with an `<<EOF>>` rule, a last,
+/// empty token of that rule is returned.
+pub struct Lexer<'a> {
+    dfa: &'a LexerDfa,
+    input: &'a str,
+    offset: usize,
+    modes: Vec<usize>,
+    // Whether the token of an `<<EOF>>` rule was returned.
+    ended: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(dfa: &'a LexerDfa, input: &'a str) -> Self {
+        Lexer {
+            dfa,
+            input,
+            offset: 0,
+            modes: vec![0],
+            ended: false,
+        }
+    }
+
+    /// The byte offset at which the next token starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The mode on top of the stack.
+    pub fn mode(&self) -> usize {
+        *self.modes.last().unwrap()
+    }
+
+    pub fn push_mode(&mut self, mode: usize) {
+        assert!(mode < self.dfa.starts().len(), "no such mode: {}", mode);
+
+        self.modes.push(mode);
+    }
+
+    /// Pops the mode on top of the stack and returns it, unless it is the
+    /// last one.
+    pub fn pop_mode(&mut self) -> Option<usize> {
+        if self.modes.len() > 1 {
+            self.modes.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the mode on top of the stack.
+    pub fn set_mode(&mut self, mode: usize) {
+        assert!(mode < self.dfa.starts().len(), "no such mode: {}", mode);
+
+        *self.modes.last_mut().unwrap() = mode;
+    }
+
+    /// Whether the next token starts a line.
+    pub fn at_line_start(&self) -> bool {
+        self.offset == 0 || self.input.as_bytes()[self.offset - 1] == b'\n'
+    }
+
+    /// The length and rule of the longest match at the current offset.
+    ///
+    /// The automaton is run as far as it goes, and then backs up to the last
+    /// accepting state it went through.
+    fn longest_match(&self) -> Option<(usize, usize)> {
+        let mut state = match self.at_line_start() {
+            true => self.dfa.line_starts()[self.mode()],
+            false => self.dfa.starts()[self.mode()],
+        };
+        let mut last_accept = None;
+
+        for (index, c) in self.input[self.offset..].char_indices() {
+            state = match self.dfa.next(state, c) {
+                Some(next) => next,
+                None => break,
+            };
+
+            if let Some(rule) = self.dfa.token_of(state) {
+                last_accept = Some((index + c.len_utf8(), rule));
+            }
+        }
+
+        last_accept
+    }
+
+    /// The token of the `<<EOF>>` rule of the current mode, the first time
+    /// the input runs out.
+    fn end_of_input(&mut self) -> Option<Token<'a>> {
+        if self.ended {
+            return None;
+        }
+
+        self.ended = true;
+
+        let rule = self.dfa.end_rule(self.mode())?;
+        let span = Span::new(self.offset, self.offset);
+
+        Some(Token {
+            kind: TokenKind::Rule(rule),
+            text: "",
+            span,
+        })
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = match self.input[self.offset..].chars().next() {
+            Some(c) => c,
+            None => return self.end_of_input(),
+        };
+
+        let (length, kind) = match self.longest_match() {
+            Some((length, rule)) => {
+                let length = match self.dfa.context_of(rule) {
+                    Some(context) => {
+                        context.token_len(&self.input[self.offset..self.offset + length])
+                    }
+                    None => length,
+                };
+
+                (length, TokenKind::Rule(rule))
+            }
+            None => (c.len_utf8(), TokenKind::Error),
+        };
+
+        let span = Span::new(self.offset, self.offset + length);
+
+        self.offset = span.end;
+
+        Some(Token {
+            kind,
+            text: &self.input[span.start..span.end],
+            span,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fsa::LexerNfa;
+    use crate::regex::Definitions;
+
+    fn moded_lexer_dfa(rules: &[(&str, &str)], modes: &[Vec<usize>], count: usize) -> LexerDfa {
+        let nfa = LexerNfa::from_rules_in_modes(rules, modes, count, &Definitions::new()).unwrap();
+        let mut dfa = LexerDfa::from_nfa(&nfa);
+        dfa.minimize();
+
+        dfa
+    }
+
+    fn lexer_dfa(rules: &[(&str, &str)]) -> LexerDfa {
+        let mut dfa = LexerDfa::from_nfa(&LexerNfa::from_rules(rules).unwrap());
+        dfa.minimize();
+
+        dfa
+    }
+
+    fn lex<'a>(dfa: &'a LexerDfa, input: &'a str) -> Vec<(&'a str, &'a str)> {
+        Lexer::new(dfa, input)
+            .map(|token| match token.kind {
+                TokenKind::Rule(rule) => (dfa.tokens()[rule].as_str(), token.text),
+                TokenKind::Error => ("Error", token.text),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn longest_match() {
+        let dfa = lexer_dfa(&[
+            ("If", r#" "if" "#),
+            ("Ident", "a-z (a-z | 0-9)*"),
+            ("Number", "0-9+"),
+            ("Space", r#" " "+ "#),
+            ("Eq", r#" "=" "#),
+            ("EqEq", r#" "==" "#),
+        ]);
+
+        assert_eq!(
+            lex(&dfa, "if iffy == 42"),
+            vec![
+                ("If", "if"),
+                ("Space", " "),
+                ("Ident", "iffy"),
+                ("Space", " "),
+                ("EqEq", "=="),
+                ("Space", " "),
+                ("Number", "42")
+            ]
+        );
+    }
+
+    #[test]
+    fn backtracks_to_last_accept() {
+        // After "1." the automaton still expects a fraction, but gets an 'x',
+        // so it backs up to the number.
+        let dfa = lexer_dfa(&[("Number", r#" 0-9+ ("." 0-9+)? "#), ("Dot", r#" "." "#)]);
+
+        assert_eq!(
+            lex(&dfa, "1.5 1.x"),
+            vec![
+                ("Number", "1.5"),
+                ("Error", " "),
+                ("Number", "1"),
+                ("Dot", "."),
+                ("Error", "x")
+            ]
+        );
+    }
+
+    #[test]
+    fn error_tokens() {
+        let dfa = lexer_dfa(&[("Ident", "a-z+")]);
+        let tokens: Vec<Token> = Lexer::new(&dfa, "ab#ß").collect();
+
+        assert_eq!(tokens.len(), 3);
+        assert!(tokens[1].is_error());
+        assert_eq!(tokens[1].span, Span::new(2, 3));
+        assert_eq!(tokens[2].text, "ß");
+        assert_eq!(tokens[2].span, Span::new(3, 5));
+    }
+
+    #[test]
+    fn empty_matches_are_ignored() {
+        let dfa = lexer_dfa(&[("As", "a*")]);
+
+        assert_eq!(lex(&dfa, "aab"), vec![("As", "aa"), ("Error", "b")]);
+        assert_eq!(lex(&dfa, ""), vec![]);
+    }
+
+    #[test]
+    fn spans_cover_the_input() {
+        let dfa = lexer_dfa(&[("Word", "[^ ]+"), ("Space", r#" " " "#)]);
+        let input = "héllo wörld";
+
+        let mut lexer = Lexer::new(&dfa, input);
+        let mut end = 0;
+
+        for token in lexer.by_ref() {
+            assert_eq!(token.span.start, end);
+            assert_eq!(&input[token.span.start..token.span.end], token.text);
+
+            end = token.span.end;
+        }
+
+        assert_eq!(end, input.len());
+        assert_eq!(lexer.offset(), input.len());
+    }
+
+    #[test]
+    fn modes() {
+        let dfa = moded_lexer_dfa(
+            &[
+                ("Quote", r#" "\"" "#),
+                ("Word", "a-z+"),
+                ("Text", r#" [^"]+ "#),
+                ("Space", r#" " " "#),
+            ],
+            &[vec![0, 1], vec![0], vec![1], vec![0]],
+            2,
+        );
+        let mut lexer = Lexer::new(&dfa, r#"ab "c d" e"#);
+        let mut tokens = vec![];
+
+        while let Some(token) = lexer.next() {
+            let name = match token.kind {
+                TokenKind::Rule(rule) => dfa.tokens()[rule].as_str(),
+                TokenKind::Error => "Error",
+            };
+
+            if name == "Quote" && lexer.pop_mode().is_none() {
+                lexer.push_mode(1);
+            }
+
+            tokens.push((name, token.text));
+        }
+
+        assert_eq!(
+            tokens,
+            vec![
+                ("Word", "ab"),
+                ("Space", " "),
+                ("Quote", "\""),
+                ("Text", "c d"),
+                ("Quote", "\""),
+                ("Space", " "),
+                ("Word", "e")
+            ]
+        );
+        assert_eq!(lexer.mode(), 0);
+
+        lexer.set_mode(1);
+        assert_eq!(lexer.mode(), 1);
+        assert_eq!(lexer.pop_mode(), None);
+    }
+
+    #[test]
+    fn trailing_context_is_lexed_again() {
+        let dfa = lexer_dfa(&[
+            ("Int", r#" 0-9+ / ".." "#),
+            ("Float", r#" 0-9+ "." 0-9+ "#),
+            ("Int", "0-9+"),
+            ("Range", r#" ".." "#),
+            ("If", r#" "if" / " "* "(" "#),
+            ("Ident", "a-z+"),
+            ("Space", r#" " "+ "#),
+            ("Paren", r#" "(" "#),
+        ]);
+        let tokens: Vec<(&str, &str)> = Lexer::new(&dfa, "1..2.5 if (iffy if")
+            .map(|token| match token.kind {
+                TokenKind::Rule(rule) => (dfa.tokens()[rule].as_str(), token.text),
+                TokenKind::Error => ("Error", token.text),
+            })
+            .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                ("Int", "1"),
+                ("Range", ".."),
+                ("Float", "2.5"),
+                ("Space", " "),
+                ("If", "if"),
+                ("Space", " "),
+                ("Paren", "("),
+                ("Ident", "iffy"),
+                ("Space", " "),
+                ("Ident", "if"),
+            ]
+        );
+    }
+
+    #[test]
+    fn anchors() {
+        let dfa = lexer_dfa(&[
+            ("Directive", r##" ^ "#" a-z+ "##),
+            ("Last", " a-z+ $ "),
+            ("Word", "a-z+"),
+            ("Hash", r##" "#" "##),
+            ("Space", r#" " " | "\n" "#),
+            ("Eof", "<<EOF>>"),
+        ]);
+
+        assert_eq!(
+            lex(&dfa, "#if a #b c\n#end"),
+            vec![
+                ("Directive", "#if"),
+                ("Space", " "),
+                ("Word", "a"),
+                ("Space", " "),
+                ("Hash", "#"),
+                ("Word", "b"),
+                ("Space", " "),
+                ("Last", "c"),
+                ("Space", "\n"),
+                ("Directive", "#end"),
+                ("Eof", "")
+            ]
+        );
+
+        let mut lexer = Lexer::new(&dfa, "a");
+
+        assert_eq!(lexer.nth(1).map(|token| token.span), Some(Span::new(1, 1)));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn end_of_input_per_mode() {
+        let dfa = moded_lexer_dfa(&[("A", "a"), ("End", "<<EOF>>")], &[vec![0, 1], vec![1]], 2);
+
+        assert_eq!(lex(&dfa, "a"), vec![("A", "a")]);
+
+        let mut lexer = Lexer::new(&dfa, "a");
+        lexer.push_mode(1);
+
+        let tokens: Vec<TokenKind> = lexer.map(|token| token.kind).collect();
+
+        assert_eq!(tokens, vec![TokenKind::Rule(0), TokenKind::Rule(1)]);
+    }
+}
@@ -1,5 +1,16 @@
-mod regex;
-mod fsa;
+mod builder;
+pub mod codegen;
+mod diagnostic;
+pub mod fsa;
+pub mod lexer;
+pub mod regex;
+pub mod spec;
 
-pub use regex::to_postfix;
-pub use fsa::Nfa;
+pub use builder::{BuildError, Builder};
+pub use diagnostic::Diagnostic;
+pub use fsa::{Dfa, LexerDfa, LexerNfa, Nfa};
+pub use lexer::{Lexer, Token, TokenKind};
+pub use regex::{
+    parse, to_postfix, Ast, CharClass, Definitions, Element, RegexError, Span, Spanned,
+};
+pub use spec::{Engine, Spec, SpecError};
@@ -1,3 +1,187 @@
+use std::env;
+use std::fs;
+use std::process;
+
+use rlex::{codegen, Engine, Spec};
+
+const USAGE: &str = "\
+Usage: rlex [OPTIONS] <SPEC>
+
+Generates the Rust source of the lexer described by the .rlex file SPEC.
+
+Options:
+  -o, --output <FILE>     Write the lexer to FILE instead of stdout
+      --engine <ENGINE>   Run the automaton from `table`s or `match`
+                          statements, overriding the spec
+      --check             Only check the spec, without generating code
+  -h, --help              Print this help";
+
+/// The command line of a run.
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    spec: String,
+    output: Option<String>,
+    engine: Option<Engine>,
+    check: bool,
+    help: bool,
+}
+
+fn parse_engine(name: &str) -> Result<Engine, String> {
+    match name {
+        "table" => Ok(Engine::Table),
+        "match" => Ok(Engine::Match),
+        _ => Err(format!(
+            "unknown engine: '{}', expected 'table' or 'match'",
+            name
+        )),
+    }
+}
+
+fn parse_args<I>(args: I) -> Result<Options, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut options = Options::default();
+    let mut spec = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value_of = |flag: &str| {
+            args.next()
+                .ok_or_else(|| format!("{} expects a value", flag))
+        };
+
+        match arg.as_str() {
+            "-o" | "--output" => options.output = Some(value_of(&arg)?),
+            "--engine" => options.engine = Some(parse_engine(&value_of(&arg)?)?),
+            "--check" => options.check = true,
+            "-h" | "--help" => options.help = true,
+            _ if arg.starts_with("--output=") => {
+                options.output = Some(arg["--output=".len()..].to_string())
+            }
+            _ if arg.starts_with("--engine=") => {
+                options.engine = Some(parse_engine(&arg["--engine=".len()..])?)
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ if spec.is_some() => return Err(format!("unexpected argument: {}", arg)),
+            _ => spec = Some(arg),
+        }
+    }
+
+    match spec {
+        Some(spec) => options.spec = spec,
+        None if options.help => {}
+        None => return Err("missing the spec file".to_string()),
+    }
+
+    Ok(options)
+}
+
+/// Runs the command line, returning the exit code.
+fn run(options: &Options) -> i32 {
+    let source = match fs::read_to_string(&options.spec) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("error: could not read {}: {}", options.spec, error);
+            return 1;
+        }
+    };
+
+    let built = Spec::parse(&source).and_then(|mut spec| {
+        if let Some(engine) = options.engine {
+            spec.engine = engine;
+        }
+
+        let dfa = spec.build_dfa(&source)?;
+
+        Ok((spec, dfa))
+    });
+
+    let (spec, dfa) = match built {
+        Ok(built) => built,
+        Err(error) => {
+            eprintln!("{}", error.diagnostic(&source).with_path(&options.spec));
+            return 1;
+        }
+    };
+
+    if options.check {
+        return 0;
+    }
+
+    let generated = codegen::generate(&spec, &dfa);
+
+    match &options.output {
+        Some(path) => {
+            if let Err(error) = fs::write(path, generated) {
+                eprintln!("error: could not write {}: {}", path, error);
+                return 1;
+            }
+        }
+        None => print!("{}", generated),
+    }
+
+    0
+}
+
 fn main() {
-    println!("Hello, world!");
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    process::exit(run(&options));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Result<Options, String> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn spec_and_flags() {
+        assert_eq!(
+            args("-o out.rs --engine match lexer.rlex").unwrap(),
+            Options {
+                spec: "lexer.rlex".to_string(),
+                output: Some("out.rs".to_string()),
+                engine: Some(Engine::Match),
+                ..Options::default()
+            }
+        );
+        assert_eq!(
+            args("lexer.rlex --check --output=out.rs --engine=table").unwrap(),
+            Options {
+                spec: "lexer.rlex".to_string(),
+                output: Some("out.rs".to_string()),
+                engine: Some(Engine::Table),
+                check: true,
+                ..Options::default()
+            }
+        );
+        assert!(args("--help").unwrap().help);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(args(""), Err("missing the spec file".to_string()));
+        assert_eq!(args("a.rlex -o"), Err("-o expects a value".to_string()));
+        assert_eq!(
+            args("a.rlex b.rlex"),
+            Err("unexpected argument: b.rlex".to_string())
+        );
+        assert_eq!(args("a.rlex -x"), Err("unknown option: -x".to_string()));
+        assert!(args("a.rlex --engine fast").is_err());
+    }
 }
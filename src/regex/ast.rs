@@ -0,0 +1,776 @@
+use std::fmt;
+
+use super::escape::{escape_char, unescape};
+use super::repr::Element::{self, *};
+use super::{CharClass, RegexError, Span, Spanned};
+
+/// A parsed regex as a tree.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Ast {
+    /// Matches any one of its branches.
+    Alternation(Vec<Ast>),
+    /// Matches its parts one after another.
+    Concat(Vec<Ast>),
+    /// Matches `ast` at least `min` and at most `max` times. A `max` of
+    /// `None` means there is no upper bound. `span` is the span of the
+    /// repetition operator.
+    Repeat {
+        ast: Box<Ast>,
+        min: usize,
+        max: Option<usize>,
+        span: Span,
+    },
+    /// Matches any char in the inclusive range.
+    Range(char, char),
+    /// Matches any char in the class.
+    Class(CharClass),
+    Literal(String),
+    /// Matches the empty string.
+    Empty,
+    /// A name that may refer to another pattern.
+    Reference(String, Span),
+    /// Matches the empty string at the start of a line.
+    LineStart(Span),
+    /// Matches the empty string at the end of a line.
+    LineEnd(Span),
+    /// Matches the end of the input.
+    EndOfInput(Span),
+    /// Matches `head` followed by `tail`, the trailing context, which is not
+    /// part of the token. `span` is the span of the '/'.
+    Trailing {
+        head: Box<Ast>,
+        tail: Box<Ast>,
+        span: Span,
+    },
+}
+
+enum Operand<'a> {
+    Atom(Element<'a>),
+    Ast(Ast),
+}
+
+impl<'a> Operand<'a> {
+    fn into_ast(self) -> Ast {
+        match self {
+            Operand::Atom(Char(c)) => Ast::Literal(c.to_string()),
+            Operand::Atom(Number(n)) => Ast::Literal(n.to_string()),
+            Operand::Atom(_) => unreachable!("only chars and numbers are kept as atoms"),
+            Operand::Ast(ast) => ast,
+        }
+    }
+}
+
+fn pop_ast(
+    stack: &mut Vec<Spanned<Operand>>,
+    operator_span: Span,
+) -> Result<Spanned<Ast>, RegexError> {
+    let operand = stack
+        .pop()
+        .ok_or(RegexError::MissingOperand(operator_span))?;
+
+    Ok(Spanned::new(operand.value.into_ast(), operand.span))
+}
+
+fn digit_of(n: usize) -> Option<char> {
+    std::char::from_digit(n as u32, 10)
+}
+
+impl Ast {
+    /// Builds the tree of a postfix regex, as produced by `to_postfix`.
+    pub fn from_postfix(postfix: &[Spanned<Element>]) -> Result<Ast, RegexError> {
+        let mut stack: Vec<Spanned<Operand>> = vec![];
+
+        for element in postfix {
+            let Spanned { value, span } = *element;
+
+            let operand = match value {
+                Text(raw) => {
+                    let text = unescape(raw, span.start + 1)?;
+
+                    Spanned::new(Operand::Ast(Ast::Literal(text)), span)
+                }
+                Class(raw) => {
+                    let class = CharClass::parse(raw, span.start + 1)?;
+
+                    Spanned::new(Operand::Ast(Ast::Class(class)), span)
+                }
+                NameOrText(name) => {
+                    Spanned::new(Operand::Ast(Ast::Reference(name.to_string(), span)), span)
+                }
+                Number(_) | Char(_) => Spanned::new(Operand::Atom(value), span),
+                Eps => Spanned::new(Operand::Ast(Ast::Empty), span),
+                LineStart => Spanned::new(Operand::Ast(Ast::LineStart(span)), span),
+                LineEnd => Spanned::new(Operand::Ast(Ast::LineEnd(span)), span),
+                EndOfInput => Spanned::new(Operand::Ast(Ast::EndOfInput(span)), span),
+
+                Dash => {
+                    let to = stack.pop().ok_or(RegexError::MissingOperand(span))?;
+                    let from = stack.pop().ok_or(RegexError::MissingOperand(span))?;
+                    let range_span = from.span.to(to.span);
+
+                    let range = match (from.value, to.value) {
+                        (Operand::Atom(Char(c1)), Operand::Atom(Char(c2))) if c1 <= c2 => {
+                            Ast::Range(c1, c2)
+                        }
+                        (Operand::Atom(Number(n1)), Operand::Atom(Number(n2))) if n1 <= n2 => {
+                            match (digit_of(n1), digit_of(n2)) {
+                                (Some(c1), Some(c2)) => Ast::Range(c1, c2),
+                                _ => return Err(RegexError::InvalidRange(range_span)),
+                            }
+                        }
+                        _ => return Err(RegexError::InvalidRange(range_span)),
+                    };
+
+                    Spanned::new(Operand::Ast(range), range_span)
+                }
+                Or | Concat => {
+                    let right = pop_ast(&mut stack, span)?;
+                    let left = pop_ast(&mut stack, span)?;
+                    let span = left.span.to(right.span);
+
+                    let ast = if value == Or {
+                        Ast::alternation(left.value, right.value)
+                    } else {
+                        Ast::concat(left.value, right.value)
+                    };
+
+                    Spanned::new(Operand::Ast(ast), span)
+                }
+                Slash => {
+                    let tail = pop_ast(&mut stack, span)?;
+                    let head = pop_ast(&mut stack, span)?;
+
+                    let ast = Ast::Trailing {
+                        head: Box::new(head.value),
+                        tail: Box::new(tail.value),
+                        span,
+                    };
+
+                    Spanned::new(Operand::Ast(ast), head.span.to(tail.span))
+                }
+                Star | Plus | Question | Repeat(_, _) => {
+                    let operand = pop_ast(&mut stack, span)?;
+
+                    let (min, max) = match value {
+                        Star => (0, None),
+                        Plus => (1, None),
+                        Question => (0, Some(1)),
+                        Repeat(min, max) => (min, max),
+                        _ => unreachable!(),
+                    };
+
+                    let ast = Ast::Repeat {
+                        ast: Box::new(operand.value),
+                        min,
+                        max,
+                        span,
+                    };
+
+                    Spanned::new(Operand::Ast(ast), operand.span.to(span))
+                }
+            };
+
+            stack.push(operand);
+        }
+
+        let operand = stack.pop();
+
+        if let Some(operand) = stack.pop() {
+            return Err(RegexError::MissingOperator(operand.span));
+        }
+
+        Ok(operand.map_or(Ast::Empty, |operand| operand.value.into_ast()))
+    }
+
+    /// Converts the tree back into postfix form.
+    ///
+    /// Single-char literals become `Char`s, or `Number`s for digits, and
+    /// every other literal becomes a `Text`. Alternations and concatenations
+    /// of more than two parts are chained left to right.
+    pub fn to_postfix(&self) -> Vec<Element<'_>> {
+        let mut postfix = vec![];
+
+        self.push_postfix(&mut postfix);
+
+        postfix
+    }
+
+    fn push_postfix<'a>(&'a self, postfix: &mut Vec<Element<'a>>) {
+        match self {
+            Ast::Alternation(branches) => Ast::push_chain(branches, Or, postfix),
+            Ast::Concat(parts) => Ast::push_chain(parts, Concat, postfix),
+            Ast::Repeat { ast, min, max, .. } => {
+                ast.push_postfix(postfix);
+
+                postfix.push(match (min, max) {
+                    (0, None) => Star,
+                    (1, None) => Plus,
+                    (0, Some(1)) => Question,
+                    (min, max) => Repeat(*min, *max),
+                });
+            }
+            Ast::Range(c1, c2) => Ast::push_range(*c1, *c2, postfix),
+            Ast::Class(class) => match class.ranges().split_first() {
+                Some((&(from, to), rest)) => {
+                    Ast::push_range(from, to, postfix);
+
+                    for &(from, to) in rest {
+                        Ast::push_range(from, to, postfix);
+                        postfix.push(Or);
+                    }
+                }
+                // No range can express an empty class, so fall back to the
+                // negation of every char.
+                None => postfix.push(Class(r"^\u{0}-\u{10ffff}")),
+            },
+            Ast::Literal(text) => {
+                let mut chars = text.chars();
+
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => postfix.push(Ast::atom(c)),
+                    // Texts hold raw source, so texts that would need escapes
+                    // are spelled out char by char.
+                    _ if text.contains(['\\', '"']) => {
+                        Ast::push_chain_of(text.chars().map(Ast::atom), Concat, postfix)
+                    }
+                    _ => postfix.push(Text(text)),
+                }
+            }
+            Ast::Empty => postfix.push(Eps),
+            Ast::Reference(name, _) => postfix.push(NameOrText(name)),
+            Ast::LineStart(_) => postfix.push(LineStart),
+            Ast::LineEnd(_) => postfix.push(LineEnd),
+            Ast::EndOfInput(_) => postfix.push(EndOfInput),
+            Ast::Trailing { head, tail, .. } => {
+                head.push_postfix(postfix);
+                tail.push_postfix(postfix);
+                postfix.push(Slash);
+            }
+        }
+    }
+
+    fn atom<'a>(c: char) -> Element<'a> {
+        match c.to_digit(10) {
+            Some(n) => Number(n as usize),
+            None => Char(c),
+        }
+    }
+
+    fn push_range(from: char, to: char, postfix: &mut Vec<Element>) {
+        if from == to {
+            postfix.push(Ast::atom(from));
+        } else if from.is_ascii_digit() && to.is_ascii_digit() {
+            postfix.extend([Ast::atom(from), Ast::atom(to), Dash]);
+        } else {
+            postfix.extend([Char(from), Char(to), Dash]);
+        }
+    }
+
+    fn push_chain_of<'a, I>(elements: I, operator: Element<'a>, postfix: &mut Vec<Element<'a>>)
+    where
+        I: Iterator<Item = Element<'a>>,
+    {
+        for (index, element) in elements.enumerate() {
+            postfix.push(element);
+
+            if index > 0 {
+                postfix.push(operator);
+            }
+        }
+    }
+
+    fn push_chain<'a>(asts: &'a [Ast], operator: Element<'a>, postfix: &mut Vec<Element<'a>>) {
+        match asts.split_first() {
+            Some((first, rest)) => {
+                first.push_postfix(postfix);
+
+                for ast in rest {
+                    ast.push_postfix(postfix);
+                    postfix.push(operator);
+                }
+            }
+            None => postfix.push(Eps),
+        }
+    }
+
+    /// Joins two trees with an alternation, flattening nested alternations.
+    pub fn alternation(left: Ast, right: Ast) -> Ast {
+        let mut branches = match left {
+            Ast::Alternation(branches) => branches,
+            ast => vec![ast],
+        };
+
+        match right {
+            Ast::Alternation(rest) => branches.extend(rest),
+            ast => branches.push(ast),
+        }
+
+        Ast::Alternation(branches)
+    }
+
+    /// Joins two trees with a concatenation, flattening nested concatenations.
+    pub fn concat(left: Ast, right: Ast) -> Ast {
+        let mut parts = match left {
+            Ast::Concat(parts) => parts,
+            ast => vec![ast],
+        };
+
+        match right {
+            Ast::Concat(rest) => parts.extend(rest),
+            ast => parts.push(ast),
+        }
+
+        Ast::Concat(parts)
+    }
+
+    /// The number of chars of every text the tree matches, if they all have
+    /// the same length, and it fits a `usize`. References are counted as
+    /// literal text.
+    pub fn fixed_length(&self) -> Option<usize> {
+        match self {
+            Ast::Alternation(branches) => match branches.split_first() {
+                Some((first, rest)) => {
+                    let length = first.fixed_length()?;
+
+                    rest.iter()
+                        .all(|branch| branch.fixed_length() == Some(length))
+                        .then_some(length)
+                }
+                None => Some(0),
+            },
+            Ast::Concat(parts) => parts.iter().try_fold(0usize, |sum, part| {
+                part.fixed_length()
+                    .and_then(|length| sum.checked_add(length))
+            }),
+            Ast::Repeat { ast, min, max, .. } if *max == Some(*min) => ast
+                .fixed_length()
+                .and_then(|length| length.checked_mul(*min)),
+            Ast::Repeat { .. } | Ast::Trailing { .. } => None,
+            Ast::Range(_, _) | Ast::Class(_) => Some(1),
+            Ast::Literal(text) | Ast::Reference(text, _) => Some(text.chars().count()),
+            Ast::Empty | Ast::LineStart(_) | Ast::LineEnd(_) | Ast::EndOfInput(_) => Some(0),
+        }
+    }
+
+    fn precedence(&self) -> usize {
+        match self {
+            Ast::Trailing { .. } => 0,
+            Ast::Alternation(branches) if branches.len() > 1 => 1,
+            Ast::Concat(parts) if parts.len() > 1 => 2,
+            Ast::Repeat { .. } => 3,
+            _ => 4,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter, precedence: usize) -> fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+
+    fn fmt_chain(
+        f: &mut fmt::Formatter,
+        asts: &[Ast],
+        separator: &str,
+        precedence: usize,
+    ) -> fmt::Result {
+        for (index, ast) in asts.iter().enumerate() {
+            if index > 0 {
+                write!(f, "{}", separator)?;
+            }
+
+            ast.fmt_operand(f, precedence)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Prints the tree back in the infix regex syntax, with as few parentheses as
+/// the operator priorities allow.
+impl fmt::Display for Ast {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ast::Alternation(branches) if branches.is_empty() => write!(f, "eps"),
+            Ast::Alternation(branches) => Ast::fmt_chain(f, branches, " | ", 2),
+            Ast::Concat(parts) if parts.is_empty() => write!(f, "eps"),
+            Ast::Concat(parts) => Ast::fmt_chain(f, parts, " ", 3),
+            Ast::Repeat { ast, min, max, .. } => {
+                ast.fmt_operand(f, 4)?;
+
+                match (min, max) {
+                    (0, None) => write!(f, "*"),
+                    (1, None) => write!(f, "+"),
+                    (0, Some(1)) => write!(f, "?"),
+                    (min, None) => write!(f, "{{{},}}", min),
+                    (min, Some(max)) if min == max => write!(f, "{{{}}}", min),
+                    (min, Some(max)) => write!(f, "{{{},{}}}", min, max),
+                }
+            }
+            // Only ranges of a single kind of char parse back from `c1-c2`.
+            Ast::Range(c1, c2)
+                if (c1.is_lowercase() && c2.is_lowercase())
+                    || (c1.is_uppercase() && c2.is_uppercase())
+                    || (c1.is_ascii_digit() && c2.is_ascii_digit()) =>
+            {
+                write!(f, "{}-{}", c1, c2)
+            }
+            Ast::Range(c1, c2) => write!(f, "{}", CharClass::of_range(*c1, *c2)),
+            Ast::Class(class) => write!(f, "{}", class),
+            Ast::Literal(text) => {
+                let mut escaped = String::new();
+
+                for c in text.chars() {
+                    escape_char(c, &['"'], &mut escaped);
+                }
+
+                write!(f, "\"{}\"", escaped)
+            }
+            Ast::Empty => write!(f, "eps"),
+            Ast::Reference(name, _) => write!(f, "{}", name),
+            Ast::LineStart(_) => write!(f, "^"),
+            Ast::LineEnd(_) => write!(f, "$"),
+            Ast::EndOfInput(_) => write!(f, "<<EOF>>"),
+            Ast::Trailing { head, tail, .. } => {
+                head.fmt_operand(f, 1)?;
+                write!(f, " / ")?;
+                tail.fmt_operand(f, 1)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex::{parse, to_postfix};
+
+    fn reference(name: &str, start: usize) -> Ast {
+        Ast::Reference(name.to_string(), Span::new(start, start + name.len()))
+    }
+
+    fn literal(text: &str) -> Ast {
+        Ast::Literal(text.to_string())
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(parse("").unwrap(), Ast::Empty);
+        assert_eq!(parse("eps").unwrap(), Ast::Empty);
+    }
+
+    #[test]
+    fn operands() {
+        assert_eq!(parse(r#" "if" "#).unwrap(), literal("if"));
+        assert_eq!(parse(r#" a "#).unwrap(), literal("a"));
+        assert_eq!(parse(r#" 1 "#).unwrap(), literal("1"));
+        assert_eq!(parse(r#" digit "#).unwrap(), reference("digit", 1));
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse(r#" a-z "#).unwrap(), Ast::Range('a', 'z'));
+        assert_eq!(parse(r#" 0-9 "#).unwrap(), Ast::Range('0', '9'));
+    }
+
+    #[test]
+    fn classes_and_escapes() {
+        assert_eq!(
+            parse(r#" [a-c_] "#).unwrap(),
+            Ast::Class(CharClass::parse("a-c_", 0).unwrap())
+        );
+        assert_eq!(
+            parse(r#" "a\"\u{1F600}" "#).unwrap(),
+            literal("a\"\u{1F600}")
+        );
+    }
+
+    #[test]
+    fn alternation_is_flattened() {
+        assert_eq!(
+            parse(r#" if | else | while "#).unwrap(),
+            Ast::Alternation(vec![
+                reference("if", 1),
+                reference("else", 6),
+                reference("while", 13)
+            ])
+        );
+    }
+
+    #[test]
+    fn concat_is_flattened() {
+        assert_eq!(
+            parse(r#" "r" . "l" "e" "#).unwrap(),
+            Ast::Concat(vec![literal("r"), literal("l"), literal("e")])
+        );
+    }
+
+    #[test]
+    fn repeat() {
+        let a = Box::new(literal("a"));
+
+        assert_eq!(
+            parse(r#" a* "#).unwrap(),
+            Ast::Repeat {
+                ast: a.clone(),
+                min: 0,
+                max: None,
+                span: Span::new(2, 3)
+            }
+        );
+        assert_eq!(
+            parse(r#" a+ "#).unwrap(),
+            Ast::Repeat {
+                ast: a.clone(),
+                min: 1,
+                max: None,
+                span: Span::new(2, 3)
+            }
+        );
+        assert_eq!(
+            parse(r#" a? "#).unwrap(),
+            Ast::Repeat {
+                ast: a,
+                min: 0,
+                max: Some(1),
+                span: Span::new(2, 3)
+            }
+        );
+    }
+
+    #[test]
+    fn identifier_regex() {
+        assert_eq!(
+            parse(r#" (a-z)+.(a-z | 0-9 | _ )* "#).unwrap(),
+            Ast::Concat(vec![
+                Ast::Repeat {
+                    ast: Box::new(Ast::Range('a', 'z')),
+                    min: 1,
+                    max: None,
+                    span: Span::new(6, 7)
+                },
+                Ast::Repeat {
+                    ast: Box::new(Ast::Alternation(vec![
+                        Ast::Range('a', 'z'),
+                        Ast::Range('0', '9'),
+                        literal("_")
+                    ])),
+                    min: 0,
+                    max: None,
+                    span: Span::new(24, 25)
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn malformed_postfix() {
+        let a = Spanned::new(Char('a'), Span::new(0, 1));
+        let b = Spanned::new(Char('b'), Span::new(2, 3));
+        let or = Spanned::new(Or, Span::new(4, 5));
+        let digit = Spanned::new(Number(1), Span::new(6, 7));
+        let dash = Spanned::new(Dash, Span::new(8, 9));
+
+        assert_eq!(
+            Ast::from_postfix(&[a, or]),
+            Err(RegexError::MissingOperand(Span::new(4, 5)))
+        );
+        assert_eq!(
+            Ast::from_postfix(&[a, b]),
+            Err(RegexError::MissingOperator(Span::new(0, 1)))
+        );
+        assert_eq!(
+            Ast::from_postfix(&[a, digit, dash]),
+            Err(RegexError::InvalidRange(Span::new(0, 7)))
+        );
+    }
+
+    #[test]
+    fn to_postfix_round_trip() {
+        let infix = r#" "0x" (0-9 | a-f)+ | a? . b* | eps "#;
+        let ast = parse(infix).unwrap();
+
+        assert_eq!(to_postfix(infix).unwrap(), ast.to_postfix());
+    }
+
+    #[test]
+    fn to_postfix_chains_left_to_right() {
+        let ast = parse(r#" a | b | c "#).unwrap();
+
+        assert_eq!(
+            ast.to_postfix(),
+            vec![Char('a'), Char('b'), Or, Char('c'), Or]
+        );
+    }
+
+    #[test]
+    fn to_postfix_of_bounded_repeat() {
+        let ast = parse(r#" a{2,3} | b{2,} "#).unwrap();
+
+        assert_eq!(
+            ast.to_postfix(),
+            vec![
+                Char('a'),
+                Repeat(2, Some(3)),
+                Char('b'),
+                Repeat(2, None),
+                Or
+            ]
+        );
+    }
+
+    #[test]
+    fn to_postfix_of_classes_and_escaped_text() {
+        let class = parse(r#" [a-c_0-9] "#).unwrap();
+        let text = parse(r#" "a\"" "#).unwrap();
+
+        assert_eq!(
+            class.to_postfix(),
+            vec![
+                Number(0),
+                Number(9),
+                Dash,
+                Char('_'),
+                Or,
+                Char('a'),
+                Char('c'),
+                Dash,
+                Or
+            ]
+        );
+        assert_eq!(text.to_postfix(), vec![Char('a'), Char('"'), Concat]);
+    }
+
+    #[test]
+    fn display_of_classes_and_escaped_text() {
+        let ast = parse(r#" [^\n] "a\"\\" [-+] "#).unwrap();
+        let printed = ast.to_string();
+
+        assert_eq!(printed, r#"[\u{0}-\t\u{b}-\u{10ffff}] "a\"\\" [+\-]"#);
+        assert_eq!(parse(&printed).unwrap(), ast);
+    }
+
+    #[test]
+    fn display() {
+        let ast = parse(r#" ("0x" . (0-9 | a-f))+ | (a | b) . c? | digit "#).unwrap();
+
+        assert_eq!(
+            ast.to_string(),
+            r#"("0x" (0-9 | a-f))+ | ("a" | "b") "c"? | digit"#
+        );
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let ast = parse(r#" ("0x" . (0-9 | a-f))+ | (a | b) . c? | digit "#).unwrap();
+        let reparsed = parse(&ast.to_string()).unwrap();
+
+        assert_eq!(reparsed.to_string(), ast.to_string());
+
+        for (range, printed) in &[
+            (Ast::Range('a', 'z'), "a-z"),
+            (Ast::Range('Α', 'Ω'), "Α-Ω"),
+            (Ast::Range('0', '9'), "0-9"),
+            (Ast::Range('0', 'z'), "[0-z]"),
+            (Ast::Range('A', 'z'), "[A-z]"),
+        ] {
+            assert_eq!(range.to_string(), *printed);
+            assert_eq!(parse(printed).unwrap().to_string(), *printed);
+        }
+    }
+
+    #[test]
+    fn bounded_repeat() {
+        assert_eq!(
+            parse(r#" (a | b){2,3} "#).unwrap(),
+            Ast::Repeat {
+                ast: Box::new(Ast::Alternation(vec![literal("a"), literal("b")])),
+                min: 2,
+                max: Some(3),
+                span: Span::new(8, 13)
+            }
+        );
+    }
+
+    #[test]
+    fn display_of_bounded_repeat() {
+        let ast = parse(r#" a{2} b{2,} (c d){0,3} "#).unwrap();
+
+        assert_eq!(ast.to_string(), r#""a"{2} "b"{2,} ("c" "d"){0,3}"#);
+        assert_eq!(
+            parse(&ast.to_string()).unwrap().to_string(),
+            ast.to_string()
+        );
+    }
+
+    #[test]
+    fn trailing_context() {
+        let ast = parse(r#" a+ / "(" | b "#).unwrap();
+
+        assert_eq!(
+            ast,
+            Ast::Trailing {
+                head: Box::new(Ast::Repeat {
+                    ast: Box::new(literal("a")),
+                    min: 1,
+                    max: None,
+                    span: Span::new(2, 3)
+                }),
+                tail: Box::new(Ast::Alternation(vec![literal("("), literal("b")])),
+                span: Span::new(4, 5)
+            }
+        );
+        assert_eq!(ast.to_string(), r#""a"+ / "(" | "b""#);
+        assert_eq!(
+            parse(&ast.to_string()).unwrap().to_string(),
+            ast.to_string()
+        );
+        assert_eq!(
+            ast.to_postfix(),
+            parse(&ast.to_string()).unwrap().to_postfix()
+        );
+    }
+
+    #[test]
+    fn fixed_length() {
+        let length = |pattern| parse(pattern).unwrap().fixed_length();
+
+        assert_eq!(length(r#" "if" [a-z] 0-9 "#), Some(4));
+        assert_eq!(length(r#" "ab" | c d | eps ef "#), Some(2));
+        assert_eq!(length(r#" (a | b c){3} name "#), None);
+        assert_eq!(length(r#" (a b){3} name "#), Some(10));
+        assert_eq!(length(r#" a | b c "#), None);
+        assert_eq!(length(r#" a? "#), None);
+        assert_eq!(length(r#" a{2,3} "#), None);
+        assert_eq!(length(""), Some(0));
+        assert_eq!(length(r#" "aa"{18446744073709551615} "#), None);
+        assert_eq!(
+            length(r#" "a"{9223372036854775808} "a"{9223372036854775808} "#),
+            None
+        );
+    }
+
+    #[test]
+    fn anchors() {
+        let ast = parse(r##" ^ "#" a $ "##).unwrap();
+
+        assert_eq!(
+            ast,
+            Ast::Concat(vec![
+                Ast::LineStart(Span::new(1, 2)),
+                literal("#"),
+                literal("a"),
+                Ast::LineEnd(Span::new(9, 10))
+            ])
+        );
+        assert_eq!(ast.to_string(), r##"^ "#" "a" $"##);
+        assert_eq!(ast.fixed_length(), Some(2));
+        assert_eq!(
+            parse(" <<EOF>> ").unwrap(),
+            Ast::EndOfInput(Span::new(1, 8))
+        );
+        assert_eq!(parse("<<EOF>>").unwrap().to_string(), "<<EOF>>");
+    }
+}
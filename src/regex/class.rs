@@ -0,0 +1,273 @@
+use std::fmt;
+
+use super::escape::{escape_char, read_char};
+use super::{RegexError, Span};
+
+/// A set of chars, stored as sorted, disjoint and non-adjacent inclusive
+/// ranges.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CharClass {
+    ranges: Vec<(char, char)>,
+}
+
+pub(crate) fn next_char(c: char) -> Option<char> {
+    match c {
+        '\u{D7FF}' => Some('\u{E000}'),
+        c => std::char::from_u32(c as u32 + 1),
+    }
+}
+
+pub(crate) fn prev_char(c: char) -> Option<char> {
+    match c {
+        '\u{E000}' => Some('\u{D7FF}'),
+        c => (c as u32).checked_sub(1).and_then(std::char::from_u32),
+    }
+}
+
+impl CharClass {
+    pub fn new() -> Self {
+        CharClass::default()
+    }
+
+    pub fn of_range(from: char, to: char) -> Self {
+        let mut class = CharClass::new();
+        class.push(from, to);
+
+        class
+    }
+
+    /// Adds the inclusive range `from..=to` to the class. Ranges with
+    /// `from > to` are empty and ignored.
+    pub fn push(&mut self, from: char, to: char) {
+        if from > to {
+            return;
+        }
+
+        self.ranges.push((from, to));
+        self.ranges.sort_unstable();
+
+        let mut merged: Vec<(char, char)> = Vec::with_capacity(self.ranges.len());
+
+        for &(from, to) in &self.ranges {
+            match merged.last_mut() {
+                Some((_, last_to)) if next_char(*last_to).map_or(true, |next| from <= next) => {
+                    *last_to = (*last_to).max(to);
+                }
+                _ => merged.push((from, to)),
+            }
+        }
+
+        self.ranges = merged;
+    }
+
+    /// The class of every char that is not in `self`.
+    pub fn negate(&self) -> Self {
+        let mut ranges = vec![];
+        let mut from = Some('\0');
+
+        for &(range_from, range_to) in &self.ranges {
+            if let (Some(from), Some(to)) = (from, prev_char(range_from)) {
+                if from <= to {
+                    ranges.push((from, to));
+                }
+            }
+
+            from = next_char(range_to);
+        }
+
+        if let Some(from) = from {
+            ranges.push((from, char::MAX));
+        }
+
+        CharClass { ranges }
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.ranges
+            .binary_search_by(|&(from, to)| {
+                if to < c {
+                    std::cmp::Ordering::Less
+                } else if from > c {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+
+    pub fn ranges(&self) -> &[(char, char)] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Parses the raw contents of a bracketed class, without the brackets.
+    ///
+    /// `offset` is the byte offset of `raw` in the whole pattern and is only
+    /// used for error spans.
+    pub fn parse(raw: &str, offset: usize) -> Result<Self, RegexError> {
+        let negated = raw.starts_with('^');
+        let mut index = negated as usize;
+
+        if index == raw.len() {
+            return Err(RegexError::EmptyClass(Span::new(
+                offset.saturating_sub(1),
+                offset + raw.len() + 1,
+            )));
+        }
+
+        let mut class = CharClass::new();
+
+        while index < raw.len() {
+            let start = index;
+            let (from, next) = read_char(raw, index, offset)?;
+
+            index = next;
+
+            // A '-' between two chars makes a range, anywhere else it is
+            // just a '-'.
+            if raw[index..].starts_with('-') && index + 1 < raw.len() {
+                let (to, next) = read_char(raw, index + 1, offset)?;
+
+                if from > to {
+                    return Err(RegexError::InvalidRange(Span::new(
+                        offset + start,
+                        offset + next,
+                    )));
+                }
+
+                class.push(from, to);
+                index = next;
+            } else {
+                class.push(from, from);
+            }
+        }
+
+        Ok(if negated { class.negate() } else { class })
+    }
+}
+
+/// Prints the class in the bracketed syntax accepted by `CharClass::parse`.
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let specials = ['[', ']', '-', '^'];
+        let mut out = String::from("[");
+
+        for &(from, to) in &self.ranges {
+            escape_char(from, &specials, &mut out);
+
+            if from != to {
+                out.push('-');
+                escape_char(to, &specials, &mut out);
+            }
+        }
+
+        out.push(']');
+
+        write!(f, "{}", out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class(raw: &str) -> CharClass {
+        CharClass::parse(raw, 1).unwrap()
+    }
+
+    #[test]
+    fn chars_and_ranges() {
+        assert_eq!(
+            class("a-zA-Z0-9_").ranges(),
+            &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')]
+        );
+    }
+
+    #[test]
+    fn overlapping_and_adjacent_ranges_are_merged() {
+        assert_eq!(class("a-mf-zA-Z[").ranges(), &[('A', '['), ('a', 'z')]);
+    }
+
+    #[test]
+    fn dash_at_the_edges_is_literal() {
+        assert_eq!(class("-a").ranges(), &[('-', '-'), ('a', 'a')]);
+        assert_eq!(class("a-").ranges(), &[('-', '-'), ('a', 'a')]);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            class(r#"\n\]\-\u{1F600}"#).ranges(),
+            &[('\n', '\n'), ('-', '-'), (']', ']'), ('😀', '😀')]
+        );
+        assert_eq!(class(r#"\u{0}-\t"#).ranges(), &[('\0', '\t')]);
+    }
+
+    #[test]
+    fn negated() {
+        let class = class(r#"^"\n"#);
+
+        assert_eq!(
+            class.ranges(),
+            &[('\0', '\t'), ('\u{b}', '!'), ('#', char::MAX)]
+        );
+        assert!(class.contains('a'));
+        assert!(!class.contains('"'));
+        assert!(!class.contains('\n'));
+    }
+
+    #[test]
+    fn negate_skips_surrogates() {
+        let class = CharClass::of_range('\0', '\u{D7FF}').negate();
+
+        assert_eq!(class.ranges(), &[('\u{E000}', char::MAX)]);
+        assert_eq!(class.negate(), CharClass::of_range('\0', '\u{D7FF}'));
+    }
+
+    #[test]
+    fn contains() {
+        let class = class("a-cx-z0");
+
+        assert!(class.contains('0'));
+        assert!(class.contains('b'));
+        assert!(class.contains('z'));
+        assert!(!class.contains('d'));
+        assert!(!class.contains('1'));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            CharClass::parse("az-a", 1),
+            Err(RegexError::InvalidRange(Span::new(2, 5)))
+        );
+        assert_eq!(
+            CharClass::parse("", 1),
+            Err(RegexError::EmptyClass(Span::new(0, 2)))
+        );
+        assert_eq!(
+            CharClass::parse("^", 1),
+            Err(RegexError::EmptyClass(Span::new(0, 3)))
+        );
+        assert_eq!(
+            CharClass::parse(r#"a\q"#, 1),
+            Err(RegexError::InvalidEscape(Span::new(2, 4)))
+        );
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let class = class(r#"a-z\]\-^ \n"#);
+        let printed = class.to_string();
+
+        assert_eq!(printed, r#"[\n \-\]-\^a-z]"#);
+        assert_eq!(
+            CharClass::parse(&printed[1..printed.len() - 1], 1).unwrap(),
+            class
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use super::{parse, Ast, RegexError, Span};
+
+/// Named patterns that references in other patterns can be resolved against.
+#[derive(Clone, Debug, Default)]
+pub struct Definitions {
+    patterns: HashMap<String, Ast>,
+    strict: bool,
+}
+
+impl Definitions {
+    /// Creates an empty environment in which references to undefined names
+    /// are matched as literal text.
+    pub fn new() -> Self {
+        Definitions::default()
+    }
+
+    /// Creates an empty environment in which references to undefined names
+    /// are reported as errors.
+    pub fn strict() -> Self {
+        Definitions {
+            patterns: HashMap::new(),
+            strict: true,
+        }
+    }
+
+    /// Parses `pattern` and binds it to `name`, replacing any previous
+    /// definition. References inside `pattern` are resolved lazily, so
+    /// definitions may refer to names that are defined later.
+    pub fn define(&mut self, name: &str, pattern: &str) -> Result<(), RegexError> {
+        let ast = parse(pattern)?;
+
+        self.patterns.insert(name.to_string(), ast);
+
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Ast> {
+        self.patterns.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.patterns.contains_key(name)
+    }
+
+    /// Replaces every reference in `ast` with the tree of its definition.
+    ///
+    /// Errors found while expanding a definition are reported at the span of
+    /// the reference in `ast` that led to it.
+    pub fn resolve(&self, ast: &Ast) -> Result<Ast, RegexError> {
+        self.resolve_in(ast, &mut vec![], None)
+    }
+
+    fn resolve_in<'a>(
+        &'a self,
+        ast: &'a Ast,
+        expanding: &mut Vec<&'a str>,
+        outer_span: Option<Span>,
+    ) -> Result<Ast, RegexError> {
+        let resolved = match ast {
+            Ast::Alternation(branches) => Ast::Alternation(
+                branches
+                    .iter()
+                    .map(|branch| self.resolve_in(branch, expanding, outer_span))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Ast::Concat(parts) => Ast::Concat(
+                parts
+                    .iter()
+                    .map(|part| self.resolve_in(part, expanding, outer_span))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Ast::Repeat {
+                ast,
+                min,
+                max,
+                span,
+            } => Ast::Repeat {
+                ast: Box::new(self.resolve_in(ast, expanding, outer_span)?),
+                min: *min,
+                max: *max,
+                span: outer_span.unwrap_or(*span),
+            },
+            Ast::Reference(name, span) => {
+                let span = outer_span.unwrap_or(*span);
+
+                match self.patterns.get(name) {
+                    Some(_) if expanding.contains(&name.as_str()) => {
+                        return Err(RegexError::RecursiveReference(name.clone(), span));
+                    }
+                    Some(definition) => {
+                        expanding.push(name);
+
+                        let resolved = self.resolve_in(definition, expanding, Some(span))?;
+
+                        expanding.pop();
+
+                        resolved
+                    }
+                    None if self.strict => {
+                        return Err(RegexError::UndefinedReference(name.clone(), span));
+                    }
+                    None => Ast::Literal(name.clone()),
+                }
+            }
+            Ast::Trailing { head, tail, span } => Ast::Trailing {
+                head: Box::new(self.resolve_in(head, expanding, outer_span)?),
+                tail: Box::new(self.resolve_in(tail, expanding, outer_span)?),
+                span: outer_span.unwrap_or(*span),
+            },
+            Ast::Range(_, _)
+            | Ast::Class(_)
+            | Ast::Literal(_)
+            | Ast::Empty
+            | Ast::LineStart(_)
+            | Ast::LineEnd(_)
+            | Ast::EndOfInput(_) => ast.clone(),
+        };
+
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(definitions: &Definitions, pattern: &str) -> Result<Ast, RegexError> {
+        definitions.resolve(&parse(pattern).unwrap())
+    }
+
+    #[test]
+    fn splices_definition() {
+        let mut definitions = Definitions::new();
+        definitions.define("digit", "0-9").unwrap();
+
+        assert_eq!(
+            resolve(&definitions, "digit+").unwrap(),
+            Ast::Repeat {
+                ast: Box::new(Ast::Range('0', '9')),
+                min: 1,
+                max: None,
+                span: Span::new(5, 6)
+            }
+        );
+    }
+
+    #[test]
+    fn splices_as_a_group() {
+        let mut definitions = Definitions::new();
+        definitions.define("sign", "plus | minus").unwrap();
+
+        assert_eq!(
+            resolve(&definitions, r#"sign "1""#).unwrap().to_string(),
+            r#"("plus" | "minus") "1""#
+        );
+    }
+
+    #[test]
+    fn nested_definitions() {
+        let mut definitions = Definitions::new();
+        definitions.define("number", "digit+").unwrap();
+        definitions.define("digit", "0-9").unwrap();
+
+        assert_eq!(
+            resolve(&definitions, r#"number "." number"#)
+                .unwrap()
+                .to_string(),
+            r#"0-9+ "." 0-9+"#
+        );
+    }
+
+    #[test]
+    fn falls_back_to_text() {
+        let definitions = Definitions::new();
+
+        assert_eq!(
+            resolve(&definitions, "while").unwrap(),
+            Ast::Literal("while".to_string())
+        );
+    }
+
+    #[test]
+    fn undefined_reference_in_strict_mode() {
+        let definitions = Definitions::strict();
+
+        assert_eq!(
+            resolve(&definitions, "a . while"),
+            Err(RegexError::UndefinedReference(
+                "while".to_string(),
+                Span::new(4, 9)
+            ))
+        );
+    }
+
+    #[test]
+    fn recursive_reference() {
+        let mut definitions = Definitions::new();
+        definitions.define("list", "item | item list").unwrap();
+        definitions.define("item", "a-z").unwrap();
+
+        assert_eq!(
+            resolve(&definitions, "a . list"),
+            Err(RegexError::RecursiveReference(
+                "list".to_string(),
+                Span::new(4, 8)
+            ))
+        );
+    }
+
+    #[test]
+    fn mutually_recursive_reference() {
+        let mut definitions = Definitions::new();
+        definitions.define("even", "eps | a odd").unwrap();
+        definitions.define("odd", "a even").unwrap();
+
+        assert_eq!(
+            resolve(&definitions, "odd"),
+            Err(RegexError::RecursiveReference(
+                "odd".to_string(),
+                Span::new(0, 3)
+            ))
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use std::error::Error;
+use std::fmt;
+
+use super::Span;
+use crate::diagnostic::Diagnostic;
+
+/// Errors reported while parsing a regex.
+///
+/// Every variant carries the span of the pattern at which the problem was
+/// detected, so it can be rendered as a `Diagnostic`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegexError {
+    /// A '"' that is never closed.
+    UnterminatedString(Span),
+    /// A '[' that is never closed.
+    UnterminatedClass(Span),
+    /// A backslash that does not start a known escape.
+    InvalidEscape(Span),
+    /// A class without any chars, e.g. `[]`.
+    EmptyClass(Span),
+    /// A '{' that is never closed.
+    UnterminatedRepeat(Span),
+    /// Repetition bounds that are not `{n}`, `{n,}` or `{n,m}` with `n <= m`.
+    InvalidRepeat(Span),
+    /// A repetition expanding its operand into more copies than the limit the
+    /// Nfa builder was given, counting those of enclosing repetitions.
+    RepeatTooLarge(usize, Span),
+    /// A '(' that is never closed or a ')' that was never opened.
+    UnbalancedParenthesis(Span),
+    /// An operator without its left operand, e.g. `| a` or `()`.
+    MissingOperand(Span),
+    /// A binary operator without its right operand, e.g. `a |`.
+    DanglingOperator(Span),
+    /// Two operands of a postfix regex that are not joined by an operator.
+    MissingOperator(Span),
+    /// A range whose bounds are not two characters or two digits in order.
+    InvalidRange(Span),
+    /// A character that is not part of the regex syntax outside a quoted text
+    /// or a class.
+    UnsupportedCharacter(char, Span),
+    /// A reference to a name without a definition, in strict mode.
+    UndefinedReference(String, Span),
+    /// A reference to a name whose definition refers back to itself.
+    RecursiveReference(String, Span),
+    /// A '/' inside another operator, or in a pattern that is not a rule.
+    MisplacedTrailingContext(Span),
+    /// A '/' with a variable length on either side.
+    VariableTrailingContext(Span),
+    /// A '/' after a pattern that matches the empty string.
+    EmptyTrailingHead(Span),
+    /// A '^' that does not start a rule, a '$' that does not end one or
+    /// follows a '/', or a `<<EOF>>` that is not a rule of its own.
+    MisplacedAnchor(Span),
+}
+
+impl RegexError {
+    pub fn span(&self) -> Span {
+        match self {
+            RegexError::UnterminatedString(span)
+            | RegexError::UnterminatedClass(span)
+            | RegexError::InvalidEscape(span)
+            | RegexError::EmptyClass(span)
+            | RegexError::UnterminatedRepeat(span)
+            | RegexError::InvalidRepeat(span)
+            | RegexError::RepeatTooLarge(_, span)
+            | RegexError::UnbalancedParenthesis(span)
+            | RegexError::MissingOperand(span)
+            | RegexError::DanglingOperator(span)
+            | RegexError::MissingOperator(span)
+            | RegexError::InvalidRange(span)
+            | RegexError::UnsupportedCharacter(_, span)
+            | RegexError::UndefinedReference(_, span)
+            | RegexError::RecursiveReference(_, span)
+            | RegexError::MisplacedTrailingContext(span)
+            | RegexError::VariableTrailingContext(span)
+            | RegexError::EmptyTrailingHead(span)
+            | RegexError::MisplacedAnchor(span) => *span,
+        }
+    }
+
+    /// Renders the error against `source`, the pattern it was reported for.
+    pub fn diagnostic<'a>(&self, source: &'a str) -> Diagnostic<'a> {
+        Diagnostic::new(source, self.span(), self.to_string())
+    }
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegexError::UnterminatedString(_) => write!(f, "could not find closing '\"'"),
+            RegexError::UnterminatedClass(_) => write!(f, "could not find closing ']'"),
+            RegexError::InvalidEscape(_) => write!(f, "invalid escape sequence"),
+            RegexError::EmptyClass(_) => write!(f, "character class is empty"),
+            RegexError::UnterminatedRepeat(_) => write!(f, "could not find closing '}}'"),
+            RegexError::InvalidRepeat(_) => write!(
+                f,
+                "repetition bounds must be {{n}}, {{n,}} or {{n,m}} with n <= m"
+            ),
+            RegexError::RepeatTooLarge(limit, _) => {
+                write!(f, "repetition bound exceeds the limit of {}", limit)
+            }
+            RegexError::UnbalancedParenthesis(_) => write!(f, "unbalanced parenthesis"),
+            RegexError::MissingOperand(_) => write!(f, "operator is missing its operand"),
+            RegexError::DanglingOperator(_) => write!(f, "operator is missing its right operand"),
+            RegexError::MissingOperator(_) => write!(f, "expected an operator before operand"),
+            RegexError::InvalidRange(_) => write!(
+                f,
+                "range bounds must be two characters or two digits in ascending order"
+            ),
+            RegexError::UnsupportedCharacter(c, _) => write!(f, "unsupported character: {}", c),
+            RegexError::UndefinedReference(name, _) => write!(f, "undefined name: {}", name),
+            RegexError::RecursiveReference(name, _) => {
+                write!(f, "definition of {} refers to itself", name)
+            }
+            RegexError::MisplacedTrailingContext(_) => {
+                write!(
+                    f,
+                    "trailing context is only allowed once, at the top of a rule"
+                )
+            }
+            RegexError::VariableTrailingContext(_) => write!(
+                f,
+                "the pattern before or after '/' must have a fixed length"
+            ),
+            RegexError::EmptyTrailingHead(_) => {
+                write!(f, "the pattern before '/' must not match the empty string")
+            }
+            RegexError::MisplacedAnchor(_) => write!(
+                f,
+                "'^' must start a rule, '$' must end one, and <<EOF>> must stand alone"
+            ),
+        }
+    }
+}
+
+impl Error for RegexError {}
@@ -0,0 +1,168 @@
+use super::{RegexError, Span};
+
+/// Reads the possibly escaped char starting at byte `index` of `raw`.
+///
+/// `offset` is the byte offset of `raw` in the whole pattern and is only
+/// used for error spans. Returns the char and the byte index right after it.
+pub fn read_char(raw: &str, index: usize, offset: usize) -> Result<(char, usize), RegexError> {
+    let mut chars = raw[index..].chars();
+
+    let c = chars.next().expect("read_char past the end of the text");
+
+    if c != '\\' {
+        return Ok((c, index + c.len_utf8()));
+    }
+
+    let invalid = |end: usize| RegexError::InvalidEscape(Span::new(offset + index, offset + end));
+
+    let escaped = match chars.next() {
+        Some(escaped) => escaped,
+        None => return Err(invalid(raw.len())),
+    };
+    let end = index + 1 + escaped.len_utf8();
+
+    let c = match escaped {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '0' => '\0',
+        '\\' | '"' | '\'' | '[' | ']' | '-' | '^' => escaped,
+        'u' => {
+            let rest = &raw[end..];
+
+            let close = match (rest.starts_with('{'), rest.find('}')) {
+                (true, Some(close)) => close,
+                _ => return Err(invalid(end)),
+            };
+
+            let end = end + close + 1;
+            let digits = &rest[1..close];
+
+            if digits.is_empty() || digits.len() > 6 {
+                return Err(invalid(end));
+            }
+
+            return u32::from_str_radix(digits, 16)
+                .ok()
+                .and_then(std::char::from_u32)
+                .map(|c| (c, end))
+                .ok_or_else(|| invalid(end));
+        }
+        _ => return Err(invalid(end)),
+    };
+
+    Ok((c, end))
+}
+
+/// Resolves the escapes in the raw contents of a quoted text.
+pub fn unescape(raw: &str, offset: usize) -> Result<String, RegexError> {
+    let mut text = String::with_capacity(raw.len());
+    let mut index = 0;
+
+    while index < raw.len() {
+        let (c, next) = read_char(raw, index, offset)?;
+
+        text.push(c);
+        index = next;
+    }
+
+    Ok(text)
+}
+
+/// Byte index of the first unescaped occurrence of `close` in `raw`.
+pub fn find_unescaped(raw: &str, close: char) -> Option<usize> {
+    let mut escaped = false;
+
+    for (index, c) in raw.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == close => return Some(index),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Writes `c` so that `read_char` reads it back, escaping `specials`,
+/// backslashes and chars that are not printable or are private use.
+pub fn escape_char(c: char, specials: &[char], out: &mut String) {
+    match c {
+        '\n' => out.push_str("\\n"),
+        '\t' => out.push_str("\\t"),
+        '\r' => out.push_str("\\r"),
+        '\\' => out.push_str("\\\\"),
+        c if specials.contains(&c) => {
+            out.push('\\');
+            out.push(c);
+        }
+        c if c.is_control()
+            || (c.is_whitespace() && c != ' ')
+            || ('\u{F0000}'..='\u{10FFFF}').contains(&c) =>
+        {
+            out.push_str(&format!("\\u{{{:x}}}", c as u32))
+        }
+        c => out.push(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simple_escapes() {
+        assert_eq!(unescape(r#"a\n\t\r\0\\\"\]"#, 0).unwrap(), "a\n\t\r\0\\\"]");
+    }
+
+    #[test]
+    fn unicode_escape() {
+        assert_eq!(unescape(r#"\u{1F600}!"#, 0).unwrap(), "😀!");
+        assert_eq!(unescape(r#"\u{41}"#, 0).unwrap(), "A");
+    }
+
+    #[test]
+    fn invalid_escapes() {
+        assert_eq!(
+            unescape(r#"ab\q"#, 10),
+            Err(RegexError::InvalidEscape(Span::new(12, 14)))
+        );
+        assert_eq!(
+            unescape(r#"\u{D800}"#, 0),
+            Err(RegexError::InvalidEscape(Span::new(0, 8)))
+        );
+        assert_eq!(
+            unescape(r#"\u{}"#, 0),
+            Err(RegexError::InvalidEscape(Span::new(0, 4)))
+        );
+        assert_eq!(
+            unescape(r#"\u41"#, 0),
+            Err(RegexError::InvalidEscape(Span::new(0, 2)))
+        );
+        assert_eq!(
+            unescape(r#"a\"#, 0),
+            Err(RegexError::InvalidEscape(Span::new(1, 2)))
+        );
+    }
+
+    #[test]
+    fn find_unescaped_skips_escapes() {
+        assert_eq!(find_unescaped(r#"a\"b"c"#, '"'), Some(4));
+        assert_eq!(find_unescaped(r#"a\\"b"#, '"'), Some(3));
+        assert_eq!(find_unescaped(r#"a\"b"#, '"'), None);
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        let text = "a\"\\\n\u{7}]ß";
+        let mut escaped = String::new();
+
+        for c in text.chars() {
+            escape_char(c, &['"'], &mut escaped);
+        }
+
+        assert_eq!(escaped, r#"a\"\\\n\u{7}]ß"#);
+        assert_eq!(unescape(&escaped, 0).unwrap(), text);
+    }
+}
@@ -1,110 +1,335 @@
+mod ast;
+mod class;
+mod definitions;
+mod error;
+mod escape;
 mod repr;
 
+pub use ast::Ast;
+pub use class::CharClass;
+pub use definitions::Definitions;
+pub use error::RegexError;
 pub use repr::Element::{self, *};
+pub use repr::{Span, Spanned};
 
-fn top_operator_of(stack: &Vec<char>) -> Option<Element> {
-    stack.last().and_then(|c| Element::operator_of(*c))
+pub(crate) use class::{next_char, prev_char};
+use escape::{find_unescaped, unescape};
+
+fn top_operator_of(stack: &[(char, Span)]) -> Option<Element<'static>> {
+    stack.last().and_then(|(c, _)| Element::operator_of(*c))
 }
 
-fn next_occur_of<F>(text: &Vec<char>, start: usize, pat: F) -> Option<usize>
+/// Byte offset of the first char at or after byte offset `start` matching `pat`.
+fn next_occur_of<F>(text: &str, start: usize, pat: F) -> Option<usize>
 where
-    F: Fn(&char) -> bool,
+    F: Fn(char) -> bool,
 {
-    text[start..]
-        .iter()
-        .position(|c| pat(c))
-        .map(|index| index + start)
+    text[start..].find(pat).map(|index| index + start)
 }
 
 fn ctou(c: char) -> Option<usize> {
     c.to_digit(10).map(|n| n as usize)
 }
 
-pub fn to_postfix(infix: &str) -> Vec<Element> {
-    let mut stack = vec![];
+fn push_operator<'a>(
+    postfix: &mut Vec<Spanned<Element<'a>>>,
+    operator: Element<'a>,
+    span: Span,
+) -> Result<(), RegexError> {
+    if operator == Dash {
+        if let [.., from, to] = postfix.as_slice() {
+            let is_range = match (from.value, to.value) {
+                (Char(c1), Char(c2)) => c1 <= c2,
+                (Number(n1), Number(n2)) => n1 <= n2,
+                _ => false,
+            };
+
+            if !is_range {
+                // Point at the bounds when they are single elements, and at
+                // the '-' itself when they are larger subexpressions.
+                if from.value.is_operand() && to.value.is_operand() {
+                    return Err(RegexError::InvalidRange(from.span.to(to.span)));
+                }
+
+                return Err(RegexError::InvalidRange(span));
+            }
+        }
+    }
+
+    postfix.push(Spanned::new(operator, span));
+
+    Ok(())
+}
+
+/// Moves every operator of higher or equal `priority` from the operator stack
+/// to the output.
+fn reduce_operators(
+    stack: &mut Vec<(char, Span)>,
+    postfix: &mut Vec<Spanned<Element>>,
+    priority: Option<usize>,
+) -> Result<(), RegexError> {
+    while let Some(top_operator) = top_operator_of(stack) {
+        if priority <= top_operator.priority() {
+            let (_, span) = stack.pop().unwrap();
+
+            push_operator(postfix, top_operator, span)?;
+        } else {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Pushes `operator` onto the operator stack, first moving every operator of
+/// higher or equal priority from the stack to the output.
+fn shift_operator(
+    stack: &mut Vec<(char, Span)>,
+    postfix: &mut Vec<Spanned<Element>>,
+    operator: char,
+    span: Span,
+) -> Result<(), RegexError> {
+    let priority = Element::operator_of(operator).and_then(|op| op.priority());
+
+    reduce_operators(stack, postfix, priority)?;
+
+    stack.push((operator, span));
+
+    Ok(())
+}
+
+/// Parses the contents of `{n}`, `{n,}` or `{n,m}`, without the braces.
+fn parse_repeat(raw: &str) -> Option<(usize, Option<usize>)> {
+    let bound = |raw: &str| {
+        let raw = raw.trim();
+
+        if !raw.is_empty() && raw.chars().all(|c| c.is_ascii_digit()) {
+            raw.parse().ok()
+        } else {
+            None
+        }
+    };
+
+    let (min, max) = match raw.split_once(',') {
+        Some((min, max)) if max.trim().is_empty() => (bound(min)?, None),
+        Some((min, max)) => (bound(min)?, Some(bound(max)?)),
+        None => {
+            let n = bound(raw)?;
+
+            (n, Some(n))
+        }
+    };
+
+    match max {
+        Some(max) if max < min => None,
+        _ => Some((min, max)),
+    }
+}
+
+/// Converts an infix regex into postfix form.
+///
+/// Juxtaposed operands are concatenated, so `"0x" (0-9 | a-f)+` is the same
+/// as `"0x" . (0-9 | a-f)+`.
+pub fn to_postfix(infix: &str) -> Result<Vec<Spanned<Element<'_>>>, RegexError> {
+    let mut stack: Vec<(char, Span)> = vec![];
     let mut postfix = vec![];
 
-    let mut char_index = 0;
-    let chars: Vec<char> = infix.chars().collect();
+    // Whether the next token must be an operand (or an opening parenthesis).
+    let mut expect_operand = true;
+    // The last token that was not whitespace, with its span.
+    let mut last_token: Option<(char, Span)> = None;
+
+    // Spans and offsets are in bytes, so they can slice `infix` directly.
+    let mut offset = 0;
 
-    while char_index < infix.len() {
-        let c = chars[char_index];
+    while let Some(c) = infix[offset..].chars().next() {
+        let char_span = Span::new(offset, offset + c.len_utf8());
 
         if let Some(operator) = Element::operator_of(c) {
-            while let Some(top_operator) = top_operator_of(&stack) {
-                if operator.priority() <= top_operator.priority() {
-                    postfix.push(Element::operator_of(stack.pop().unwrap()).unwrap());
-                } else {
-                    break;
-                }
+            if expect_operand {
+                return Err(RegexError::MissingOperand(char_span));
             }
 
-            stack.push(c);
+            shift_operator(&mut stack, &mut postfix, c, char_span)?;
 
-            char_index += 1;
-        } else {
-            match c {
-                '(' => {
-                    stack.push('(');
-                    char_index += 1;
+            expect_operand = !operator.is_unary();
+            last_token = Some((c, char_span));
+            offset = char_span.end;
+
+            continue;
+        }
+
+        match c {
+            '(' => {
+                if !expect_operand {
+                    let concat_span = Span::new(offset, offset);
+
+                    shift_operator(&mut stack, &mut postfix, '.', concat_span)?;
                 }
-                ')' => {
-                    while let Some(c) = stack.pop() {
-                        if c != '(' {
-                            postfix.push(Element::operator_of(c).unwrap());
-                        } else {
-                            break;
+
+                stack.push(('(', char_span));
+                expect_operand = true;
+                last_token = Some(('(', char_span));
+                offset = char_span.end;
+            }
+            ')' => {
+                if !stack.iter().any(|(c, _)| *c == '(') {
+                    return Err(RegexError::UnbalancedParenthesis(char_span));
+                }
+
+                if expect_operand {
+                    return match last_token {
+                        Some((operator, span)) if operator != '(' => {
+                            Err(RegexError::DanglingOperator(span))
                         }
+                        _ => Err(RegexError::MissingOperand(char_span)),
+                    };
+                }
+
+                while let Some((c, span)) = stack.pop() {
+                    if c != '(' {
+                        push_operator(&mut postfix, Element::operator_of(c).unwrap(), span)?;
+                    } else {
+                        break;
                     }
+                }
 
-                    char_index += 1;
+                last_token = Some((')', char_span));
+                offset = char_span.end;
+            }
+            '{' => {
+                if expect_operand {
+                    return Err(RegexError::MissingOperand(char_span));
                 }
-                '"' => {
-                    if let Some(index) = next_occur_of(&chars, char_index + 1, |c| *c == '"') {
-                        postfix.push(Text(&infix[char_index + 1..index]));
 
-                        char_index = index + 1;
-                    } else {
-                        panic!("Could not find closing '\"'")
+                let close = match next_occur_of(infix, char_span.end, |c| c == '}') {
+                    Some(close) => close,
+                    None => {
+                        return Err(RegexError::UnterminatedRepeat(Span::new(
+                            offset,
+                            infix.len(),
+                        )));
                     }
+                };
+
+                let span = Span::new(offset, close + 1);
+
+                let (min, max) = parse_repeat(&infix[char_span.end..close])
+                    .ok_or(RegexError::InvalidRepeat(span))?;
+
+                // Like the other unary operators, a repetition binds to the
+                // operand right before it, so it can go straight to the output.
+                let operator = Element::Repeat(min, max);
+
+                reduce_operators(&mut stack, &mut postfix, operator.priority())?;
+                push_operator(&mut postfix, operator, span)?;
+
+                last_token = Some(('}', span));
+                offset = span.end;
+            }
+            ' ' | '\t' | '\n' => offset = char_span.end,
+            c => {
+                let is_anchor = c == '^' || c == '$' || infix[offset..].starts_with("<<EOF>>");
+
+                if !(c == '"' || c == '[' || c.is_alphanumeric() || c == '_' || is_anchor) {
+                    return Err(RegexError::UnsupportedCharacter(c, char_span));
                 }
-                c if c.is_alphanumeric() || c == '_' => {
-                    let index = next_occur_of(&chars, char_index + 1, |c| {
-                        !c.is_alphanumeric() && *c != '_'
-                    })
-                    .unwrap_or(chars.len());
-
-                    if infix[char_index..index].len() == 1 {
-                        if chars[char_index].is_numeric() {
-                            postfix.push(Number(ctou(chars[char_index]).unwrap()))
-                        } else if chars[char_index].is_ascii_alphabetic()
-                            || chars[char_index] == '_'
-                        {
-                            postfix.push(Char(chars[char_index]))
+
+                let (element, end) = if is_anchor {
+                    match c {
+                        '^' => (LineStart, char_span.end),
+                        '$' => (LineEnd, char_span.end),
+                        _ => (EndOfInput, offset + "<<EOF>>".len()),
+                    }
+                } else if c == '"' || c == '[' {
+                    let close = if c == '"' { '"' } else { ']' };
+
+                    let index = match find_unescaped(&infix[char_span.end..], close) {
+                        Some(index) => char_span.end + index,
+                        None if c == '"' => {
+                            return Err(RegexError::UnterminatedString(Span::new(
+                                offset,
+                                infix.len(),
+                            )));
                         }
+                        None => {
+                            return Err(RegexError::UnterminatedClass(Span::new(
+                                offset,
+                                infix.len(),
+                            )));
+                        }
+                    };
+
+                    let raw = &infix[char_span.end..index];
+
+                    // Validate the contents here, so the postfix output is
+                    // always well-formed.
+                    if c == '"' {
+                        unescape(raw, char_span.end)?;
+
+                        (Text(raw), index + 1)
                     } else {
-                        if &infix[char_index..index] == "eps" {
-                            postfix.push(Eps)
+                        CharClass::parse(raw, char_span.end)?;
+
+                        (Class(raw), index + 1)
+                    }
+                } else {
+                    let index =
+                        next_occur_of(infix, char_span.end, |c| !c.is_alphanumeric() && c != '_')
+                            .unwrap_or(infix.len());
+
+                    let token = &infix[offset..index];
+
+                    let element = if index == char_span.end {
+                        if c.is_ascii_digit() {
+                            Number(ctou(c).unwrap())
                         } else {
-                            postfix.push(NameOrText(&infix[char_index..index]));
+                            Char(c)
                         }
-                    }
+                    } else if token == "eps" {
+                        Eps
+                    } else {
+                        NameOrText(token)
+                    };
+
+                    (element, index)
+                };
 
-                    char_index = index;
+                let span = Span::new(offset, end);
+
+                if !expect_operand {
+                    let concat_span = Span::new(offset, offset);
+
+                    shift_operator(&mut stack, &mut postfix, '.', concat_span)?;
                 }
-                ' ' | '\t' | '\n' => char_index += 1,
-                c => panic!("Unsupported character: {}", c),
+
+                postfix.push(Spanned::new(element, span));
+
+                expect_operand = false;
+                last_token = Some((c, span));
+                offset = end;
             }
         }
     }
 
-    while let Some(c) = stack.pop() {
-        let op = Element::operator_of(c).expect("Expected operator but found parenthesis");
+    if let Some((_, span)) = stack.iter().rev().find(|(c, _)| *c == '(') {
+        return Err(RegexError::UnbalancedParenthesis(*span));
+    }
 
-        postfix.push(op);
+    if let (true, Some((_, span))) = (expect_operand, last_token) {
+        return Err(RegexError::DanglingOperator(span));
     }
 
-    postfix
+    while let Some((c, span)) = stack.pop() {
+        push_operator(&mut postfix, Element::operator_of(c).unwrap(), span)?;
+    }
+
+    Ok(postfix)
+}
+
+/// Parses an infix regex into its tree.
+pub fn parse(infix: &str) -> Result<Ast, RegexError> {
+    Ast::from_postfix(&to_postfix(infix)?)
 }
 
 #[cfg(test)]
@@ -113,42 +338,42 @@ mod tests {
 
     #[test]
     fn empty_string() {
-        let postfix = to_postfix(r#""#);
+        let postfix = to_postfix(r#""#).unwrap();
 
         assert!(postfix.is_empty());
     }
 
     #[test]
     fn one_text() {
-        let postfix = to_postfix(r#" "if" "#);
+        let postfix = to_postfix(r#" "if" "#).unwrap();
 
         assert_eq!(postfix, vec![Text("if")]);
     }
 
     #[test]
     fn one_char() {
-        let postfix = to_postfix(r#" a "#);
+        let postfix = to_postfix(r#" a "#).unwrap();
 
         assert_eq!(postfix, vec![Char('a')]);
     }
 
     #[test]
     fn one_number() {
-        let postfix = to_postfix(r#" 1 "#);
+        let postfix = to_postfix(r#" 1 "#).unwrap();
 
         assert_eq!(postfix, vec![Number(1)]);
     }
 
     #[test]
     fn one_name_or_text() {
-        let postfix = to_postfix(r#" if "#);
+        let postfix = to_postfix(r#" if "#).unwrap();
 
         assert_eq!(postfix, vec![NameOrText("if")]);
     }
 
     #[test]
     fn or() {
-        let postfix = to_postfix(r#" if | else | while | for "#);
+        let postfix = to_postfix(r#" if | else | while | for "#).unwrap();
 
         assert_eq!(
             postfix,
@@ -166,7 +391,7 @@ mod tests {
 
     #[test]
     fn concat() {
-        let postfix = to_postfix(r#" "r"."l"."e"."x" "#);
+        let postfix = to_postfix(r#" "r"."l"."e"."x" "#).unwrap();
 
         assert_eq!(
             postfix,
@@ -184,35 +409,35 @@ mod tests {
 
     #[test]
     fn star() {
-        let postfix = to_postfix(r#" a* "#);
+        let postfix = to_postfix(r#" a* "#).unwrap();
 
         assert_eq!(postfix, vec![Char('a'), Star]);
     }
 
     #[test]
     fn question() {
-        let postfix = to_postfix(r#" a? "#);
+        let postfix = to_postfix(r#" a? "#).unwrap();
 
         assert_eq!(postfix, vec![Char('a'), Question])
     }
 
     #[test]
     fn plus() {
-        let postfix = to_postfix(r#" a+ "#);
+        let postfix = to_postfix(r#" a+ "#).unwrap();
 
         assert_eq!(postfix, vec![Char('a'), Plus]);
     }
 
     #[test]
     fn dash() {
-        let postfix = to_postfix(r#" a-z "#);
+        let postfix = to_postfix(r#" a-z "#).unwrap();
 
         assert_eq!(postfix, vec![Char('a'), Char('z'), Dash])
     }
 
     #[test]
     fn parenthesis() {
-        let postfix = to_postfix(r#" a . (b | c) . d "#);
+        let postfix = to_postfix(r#" a . (b | c) . d "#).unwrap();
 
         assert_eq!(
             postfix,
@@ -230,7 +455,7 @@ mod tests {
 
     #[test]
     fn identifier_regex() {
-        let postfix = to_postfix(r#" (a-z)+.(a-z | 0-9 | _ )* "#);
+        let postfix = to_postfix(r#" (a-z)+.(a-z | 0-9 | _ )* "#).unwrap();
 
         assert_eq!(
             postfix,
@@ -256,7 +481,7 @@ mod tests {
 
     #[test]
     fn floating_point_number_regex() {
-        let postfix = to_postfix(r#" 0-9+.".".0-9+ "#);
+        let postfix = to_postfix(r#" 0-9+.".".0-9+ "#).unwrap();
 
         assert_eq!(
             postfix,
@@ -278,11 +503,393 @@ mod tests {
 
     #[test]
     fn eps() {
-        let postfix = to_postfix(r#" a . (b | eps) . c "#);
+        let postfix = to_postfix(r#" a . (b | eps) . c "#).unwrap();
 
         assert_eq!(
             postfix,
             vec![Char('a'), Char('b'), Eps, Or, Concat, Char('c'), Concat]
         )
     }
+
+    #[test]
+    fn unterminated_string() {
+        assert_eq!(
+            to_postfix(r#"a . "if"#),
+            Err(RegexError::UnterminatedString(Span::new(4, 7)))
+        );
+    }
+
+    #[test]
+    fn unbalanced_parenthesis() {
+        assert_eq!(
+            to_postfix(r#"a . (b | c"#),
+            Err(RegexError::UnbalancedParenthesis(Span::new(4, 5)))
+        );
+        assert_eq!(
+            to_postfix(r#"a . b) | c"#),
+            Err(RegexError::UnbalancedParenthesis(Span::new(5, 6)))
+        );
+    }
+
+    #[test]
+    fn missing_operand() {
+        assert_eq!(
+            to_postfix(r#"| a"#),
+            Err(RegexError::MissingOperand(Span::new(0, 1)))
+        );
+        assert_eq!(
+            to_postfix(r#"a | *"#),
+            Err(RegexError::MissingOperand(Span::new(4, 5)))
+        );
+        assert_eq!(
+            to_postfix(r#"a . ()"#),
+            Err(RegexError::MissingOperand(Span::new(5, 6)))
+        );
+    }
+
+    #[test]
+    fn dangling_operator() {
+        assert_eq!(
+            to_postfix(r#"a |"#),
+            Err(RegexError::DanglingOperator(Span::new(2, 3)))
+        );
+        assert_eq!(
+            to_postfix(r#"(a .) . b"#),
+            Err(RegexError::DanglingOperator(Span::new(3, 4)))
+        );
+    }
+
+    #[test]
+    fn implicit_concat() {
+        let postfix = to_postfix(r#" "0x" (0-9 | a-f)+ "#).unwrap();
+
+        assert_eq!(
+            postfix,
+            vec![
+                Text("0x"),
+                Number(0),
+                Number(9),
+                Dash,
+                Char('a'),
+                Char('f'),
+                Dash,
+                Or,
+                Plus,
+                Concat,
+            ]
+        );
+    }
+
+    #[test]
+    fn implicit_concat_of_operands() {
+        let postfix = to_postfix(r#" a b* "c" "#).unwrap();
+
+        assert_eq!(
+            postfix,
+            vec![Char('a'), Char('b'), Star, Concat, Text("c"), Concat]
+        );
+    }
+
+    #[test]
+    fn implicit_concat_of_groups() {
+        let postfix = to_postfix(r#" (a | b)(c)? d "#).unwrap();
+
+        assert_eq!(
+            postfix,
+            vec![
+                Char('a'),
+                Char('b'),
+                Or,
+                Char('c'),
+                Question,
+                Concat,
+                Char('d'),
+                Concat
+            ]
+        );
+    }
+
+    #[test]
+    fn implicit_concat_binds_tighter_than_or() {
+        let postfix = to_postfix(r#" a b | c . d "#).unwrap();
+
+        assert_eq!(
+            postfix,
+            vec![
+                Char('a'),
+                Char('b'),
+                Concat,
+                Char('c'),
+                Char('d'),
+                Concat,
+                Or
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_range() {
+        assert_eq!(
+            to_postfix(r#"z-a"#),
+            Err(RegexError::InvalidRange(Span::new(0, 3)))
+        );
+        assert_eq!(
+            to_postfix(r#"a-9"#),
+            Err(RegexError::InvalidRange(Span::new(0, 3)))
+        );
+        assert_eq!(
+            to_postfix(r#"(a | b)-c"#),
+            Err(RegexError::InvalidRange(Span::new(7, 8)))
+        );
+    }
+
+    #[test]
+    fn unsupported_character() {
+        assert_eq!(
+            to_postfix(r#"a . #"#),
+            Err(RegexError::UnsupportedCharacter('#', Span::new(4, 5)))
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let postfix = to_postfix(r#" "if" | a-z "#).unwrap();
+        let spans: Vec<Span> = postfix.iter().map(|element| element.span).collect();
+
+        assert_eq!(
+            spans,
+            vec![
+                Span::new(1, 5),
+                Span::new(8, 9),
+                Span::new(10, 11),
+                Span::new(9, 10),
+                Span::new(6, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostic() {
+        let pattern = r#"a . "if"#;
+        let error = to_postfix(pattern).unwrap_err();
+
+        assert_eq!(
+            error.diagnostic(pattern).to_string(),
+            "error: could not find closing '\"'\n --> 1:5\n  |\n1 | a . \"if\n  |     ^~~"
+        );
+    }
+
+    #[test]
+    fn unicode_text() {
+        let postfix = to_postfix(r#" "سلام" | "你好" . "🚀" "#).unwrap();
+
+        assert_eq!(
+            postfix,
+            vec![Text("سلام"), Text("你好"), Text("🚀"), Concat, Or]
+        );
+    }
+
+    #[test]
+    fn unicode_names_and_chars() {
+        let postfix = to_postfix(r#" اگر | ß | α-ω | ۰-۹ "#).unwrap();
+
+        assert_eq!(
+            postfix,
+            vec![
+                NameOrText("اگر"),
+                Char('ß'),
+                Or,
+                Char('α'),
+                Char('ω'),
+                Dash,
+                Or,
+                Char('۰'),
+                Char('۹'),
+                Dash,
+                Or,
+            ]
+        );
+    }
+
+    #[test]
+    fn unicode_spans_are_byte_offsets() {
+        let postfix = to_postfix(r#""你好" α"#).unwrap();
+        let spans: Vec<Span> = postfix.iter().map(|element| element.span).collect();
+
+        assert_eq!(
+            spans,
+            vec![Span::new(0, 8), Span::new(9, 11), Span::new(9, 9)]
+        );
+    }
+
+    #[test]
+    fn unicode_errors() {
+        assert_eq!(
+            to_postfix(r#""你好" 🚀"#),
+            Err(RegexError::UnsupportedCharacter('🚀', Span::new(9, 13)))
+        );
+        assert_eq!(
+            to_postfix(r#"α . "ω"#),
+            Err(RegexError::UnterminatedString(Span::new(5, 8)))
+        );
+    }
+
+    #[test]
+    fn unicode_diagnostic() {
+        let pattern = r#""你好" | 🚀"#;
+        let error = to_postfix(pattern).unwrap_err();
+
+        assert_eq!(
+            error.diagnostic(pattern).to_string(),
+            "error: unsupported character: 🚀\n --> 1:8\n  |\n1 | \"你好\" | 🚀\n  |        ^"
+        );
+    }
+
+    #[test]
+    fn class() {
+        let postfix = to_postfix(r#" [a-zA-Z_][^"\n]* "#).unwrap();
+
+        assert_eq!(
+            postfix,
+            vec![Class("a-zA-Z_"), Class(r#"^"\n"#), Star, Concat]
+        );
+    }
+
+    #[test]
+    fn escaped_quote_in_text() {
+        let postfix = to_postfix(r#" "a\"b" | "\\" "#).unwrap();
+
+        assert_eq!(postfix, vec![Text(r#"a\"b"#), Text(r#"\\"#), Or]);
+    }
+
+    #[test]
+    fn escaped_bracket_in_class() {
+        let postfix = to_postfix(r#" [\]\\] "#).unwrap();
+
+        assert_eq!(postfix, vec![Class(r#"\]\\"#)]);
+    }
+
+    #[test]
+    fn class_and_escape_errors() {
+        assert_eq!(
+            to_postfix(r#"a [b-z"#),
+            Err(RegexError::UnterminatedClass(Span::new(2, 6)))
+        );
+        assert_eq!(
+            to_postfix(r#"a [z-b]"#),
+            Err(RegexError::InvalidRange(Span::new(3, 6)))
+        );
+        assert_eq!(
+            to_postfix(r#"a []"#),
+            Err(RegexError::EmptyClass(Span::new(2, 4)))
+        );
+        assert_eq!(
+            to_postfix(r#"a "b\q""#),
+            Err(RegexError::InvalidEscape(Span::new(4, 6)))
+        );
+        assert_eq!(
+            to_postfix(r#"a "b\""#),
+            Err(RegexError::UnterminatedString(Span::new(2, 6)))
+        );
+    }
+
+    #[test]
+    fn bounded_repeat() {
+        let postfix = to_postfix(r#" a{3} b{2,} c{ 1 , 4 } "#).unwrap();
+
+        assert_eq!(
+            postfix,
+            vec![
+                Char('a'),
+                Repeat(3, Some(3)),
+                Char('b'),
+                Repeat(2, None),
+                Concat,
+                Char('c'),
+                Repeat(1, Some(4)),
+                Concat
+            ]
+        );
+        assert_eq!(postfix[1].span, Span::new(2, 5));
+    }
+
+    #[test]
+    fn bounded_repeat_binds_like_star() {
+        let postfix = to_postfix(r#" a | 0-9{4} "#).unwrap();
+
+        assert_eq!(
+            postfix,
+            vec![
+                Char('a'),
+                Number(0),
+                Number(9),
+                Dash,
+                Repeat(4, Some(4)),
+                Or
+            ]
+        );
+    }
+
+    #[test]
+    fn bounded_repeat_errors() {
+        assert_eq!(
+            to_postfix(r#"a{2"#),
+            Err(RegexError::UnterminatedRepeat(Span::new(1, 3)))
+        );
+        assert_eq!(
+            to_postfix(r#"a{3,2}"#),
+            Err(RegexError::InvalidRepeat(Span::new(1, 6)))
+        );
+        assert_eq!(
+            to_postfix(r#"a{,2} b"#),
+            Err(RegexError::InvalidRepeat(Span::new(1, 5)))
+        );
+        assert_eq!(
+            to_postfix(r#"a{x}"#),
+            Err(RegexError::InvalidRepeat(Span::new(1, 4)))
+        );
+        assert_eq!(
+            to_postfix(r#"{2}"#),
+            Err(RegexError::MissingOperand(Span::new(0, 1)))
+        );
+        assert_eq!(
+            to_postfix(r#"a | {2}"#),
+            Err(RegexError::MissingOperand(Span::new(4, 5)))
+        );
+    }
+
+    #[test]
+    fn trailing_context_binds_loosest() {
+        let postfix = to_postfix(r#" a b / c | d "#).unwrap();
+
+        assert_eq!(
+            postfix,
+            vec![
+                Char('a'),
+                Char('b'),
+                Concat,
+                Char('c'),
+                Char('d'),
+                Or,
+                Slash
+            ]
+        );
+        assert_eq!(
+            to_postfix(r#" a / "#),
+            Err(RegexError::DanglingOperator(Span::new(3, 4)))
+        );
+    }
+
+    #[test]
+    fn anchors() {
+        assert_eq!(
+            to_postfix(r#" ^ a $ "#).unwrap(),
+            vec![LineStart, Char('a'), Concat, LineEnd, Concat]
+        );
+        assert_eq!(to_postfix("<<EOF>>").unwrap(), vec![EndOfInput]);
+        assert_eq!(
+            to_postfix("<EOF>"),
+            Err(RegexError::UnsupportedCharacter('<', Span::new(0, 1)))
+        );
+    }
 }
@@ -7,13 +7,27 @@ pub enum Element<'a> {
     Or,
     Concat,
     Question,
+    /// `{n}`, `{n,}` or `{n,m}`: at least `n` and at most `m` repetitions.
+    Repeat(usize, Option<usize>),
+    /// `r/s`: `r`, but only when followed by `s`.
+    Slash,
 
     // Operands
+    //
+    // `Text` and `Class` hold the raw source between the quotes or brackets,
+    // with escapes left in place.
     Text(&'a str),
     Number(usize),
     Char(char),
     NameOrText(&'a str),
+    Class(&'a str),
     Eps,
+    /// `^`: the start of a line.
+    LineStart,
+    /// `$`: the end of a line.
+    LineEnd,
+    /// `<<EOF>>`: the end of the input.
+    EndOfInput,
 }
 
 impl<'a> Element<'a> {
@@ -25,6 +39,7 @@ impl<'a> Element<'a> {
             '|' => Some(Element::Or),
             '.' => Some(Element::Concat),
             '?' => Some(Element::Question),
+            '/' => Some(Element::Slash),
             _ => None,
         }
     }
@@ -36,13 +51,19 @@ impl<'a> Element<'a> {
             | Element::Star
             | Element::Or
             | Element::Concat
-            | Element::Question => true,
+            | Element::Question
+            | Element::Repeat(_, _)
+            | Element::Slash => true,
 
             Element::Text(_)
             | Element::Number(_)
             | Element::Char(_)
             | Element::NameOrText(_)
-            | Element::Eps => false,
+            | Element::Class(_)
+            | Element::Eps
+            | Element::LineStart
+            | Element::LineEnd
+            | Element::EndOfInput => false,
         }
     }
 
@@ -50,18 +71,67 @@ impl<'a> Element<'a> {
         !self.is_operator()
     }
 
+    pub fn is_unary(&self) -> bool {
+        matches!(
+            self,
+            Element::Plus | Element::Star | Element::Question | Element::Repeat(_, _)
+        )
+    }
+
     pub fn priority(&self) -> Option<usize> {
         match self {
-            Element::Dash => Some(3),
-            Element::Plus | Element::Star | Element::Question => Some(2),
-            Element::Concat => Some(1),
-            Element::Or => Some(0),
+            Element::Dash => Some(4),
+            Element::Plus | Element::Star | Element::Question | Element::Repeat(_, _) => Some(3),
+            Element::Concat => Some(2),
+            Element::Or => Some(1),
+            Element::Slash => Some(0),
 
             Element::Text(_)
             | Element::Number(_)
             | Element::Char(_)
             | Element::NameOrText(_)
-            | Element::Eps => None,
+            | Element::Class(_)
+            | Element::Eps
+            | Element::LineStart
+            | Element::LineEnd
+            | Element::EndOfInput => None,
         }
     }
 }
+
+/// A range of byte offsets into the source of a regex.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+/// A value paired with the span of source it was parsed from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(value: T, span: Span) -> Self {
+        Spanned { value, span }
+    }
+}
+
+impl<T: PartialEq> PartialEq<T> for Spanned<T> {
+    fn eq(&self, other: &T) -> bool {
+        self.value == *other
+    }
+}
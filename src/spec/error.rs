@@ -0,0 +1,115 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::diagnostic::Diagnostic;
+use crate::regex::{RegexError, Span};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpecErrorKind {
+    /// The spec has no `%%` line starting the rules section.
+    MissingRulesSection,
+    /// The rules section has no rules.
+    NoRules,
+    /// A definition line that does not start with a name.
+    InvalidName,
+    /// A name that is defined twice.
+    DuplicateDefinition(String),
+    /// A definition or rule without a pattern.
+    MissingPattern,
+    /// A rule without `=>` and a token name or action.
+    MissingAction,
+    /// A '{' starting an action that is never closed.
+    UnterminatedAction,
+    /// Text after the '}' closing an action.
+    TrailingText,
+    /// What follows `=>` is neither a token name nor an action.
+    InvalidTokenName(String),
+    /// A token name that is a Rust keyword, which the generated `Token` enum
+    /// cannot have as a variant.
+    KeywordTokenName(String),
+    /// A line starting with '%' that is not a known directive.
+    UnknownDirective(String),
+    /// An `%option` that is not a known option.
+    UnknownOption(String),
+    /// A mode that is declared twice.
+    DuplicateMode(String),
+    /// A '<' starting the modes of a rule that is never closed.
+    UnterminatedModes,
+    /// A rule active in a mode that is not declared.
+    UnknownMode(String),
+    /// A pattern that is not a valid regex. The span of the error is
+    /// relative to the pattern.
+    Regex(RegexError),
+}
+
+/// An error in a spec, with the span and the one-based line and column (in
+/// chars) of the spec source at which it was found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpecError {
+    pub kind: SpecErrorKind,
+    pub span: Span,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SpecError {
+    pub fn new(source: &str, span: Span, kind: SpecErrorKind) -> Self {
+        let (line, column) = Diagnostic::new(source, span, "").location();
+
+        SpecError {
+            kind,
+            span,
+            line,
+            column,
+        }
+    }
+
+    /// Renders the error against `source`, the spec it was reported for.
+    pub fn diagnostic<'a>(&self, source: &'a str) -> Diagnostic<'a> {
+        Diagnostic::new(source, self.span, self.kind.to_string())
+    }
+}
+
+impl fmt::Display for SpecErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpecErrorKind::MissingRulesSection => write!(f, "expected '%%' before the rules"),
+            SpecErrorKind::NoRules => write!(f, "spec has no rules"),
+            SpecErrorKind::InvalidName => write!(f, "expected a name to define"),
+            SpecErrorKind::DuplicateDefinition(name) => write!(f, "{} is defined twice", name),
+            SpecErrorKind::MissingPattern => write!(f, "expected a pattern"),
+            SpecErrorKind::MissingAction => {
+                write!(
+                    f,
+                    "expected '=>' and a token name or action after the pattern"
+                )
+            }
+            SpecErrorKind::UnterminatedAction => write!(f, "could not find closing '}}'"),
+            SpecErrorKind::TrailingText => write!(f, "unexpected text after the action"),
+            SpecErrorKind::InvalidTokenName(name) => write!(f, "invalid token name: {}", name),
+            SpecErrorKind::KeywordTokenName(name) => {
+                write!(f, "token name is a Rust keyword: {}", name)
+            }
+            SpecErrorKind::UnknownDirective(name) => write!(f, "unknown directive: {}", name),
+            SpecErrorKind::UnknownOption(option) => {
+                write!(
+                    f,
+                    "unknown option: '{}', expected 'table' or 'match'",
+                    option
+                )
+            }
+            SpecErrorKind::DuplicateMode(name) => write!(f, "mode {} is declared twice", name),
+            SpecErrorKind::UnterminatedModes => write!(f, "could not find closing '>'"),
+            SpecErrorKind::UnknownMode(name) => write!(f, "unknown mode: '{}'", name),
+            SpecErrorKind::Regex(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl Error for SpecError {}
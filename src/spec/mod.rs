@@ -0,0 +1,1009 @@
+//! The `.rlex` lexer specification format.
+//!
+//! A spec has up to three sections separated by lines holding only `%%`:
+//!
+//! ```text
+//! digit   0-9
+//! ident   (a-z | _) (a-z | _ | digit)*
+//!
+//! %%
+//!
+//! "if"        => If
+//! ident       => Ident
+//! digit+      => Number
+//! " " | "\n"  => { None }
+//!
+//! %%
+//!
+//! // Rust code copied to the generated lexer.
+//! ```
+//!
+//! Every definition binds a name to a pattern, in the regex syntax of
+//! `regex::parse`. Every rule maps a pattern to either a token name or an
+//! action between braces, which may span several lines. A rule pattern may
+//! end in trailing context, as in `digit+ / ".."`: the part after the '/' has
+//! to follow for the rule to match, but is left for the next token. One side
+//! of the '/' must match texts of a single length. Lines starting with
+//! `//` in the first two sections are comments. The user code section is
+//! optional.
+//!
+//! A rule pattern starting with `^` only matches at the start of a line, and
+//! one ending in `$` only before a '\n', which is left for the next token as
+//! with trailing context. A rule whose pattern is `<<EOF>>` matches the end
+//! of the input, once, and its token or action gets an empty `text`.
+//!
+//! Lines starting with `%` in the first section are directives. `%option
+//! table` and `%option match` choose how the generated lexer runs its
+//! automaton: from transition tables, which is the default, or from `match`
+//! statements on the state and char.
+//!
+//! `%x NAME...` declares exclusive modes and `%s NAME...` inclusive ones. The
+//! lexer starts in the `INITIAL` mode and only matches the rules active in
+//! its current mode, which actions switch with `push_mode`, `pop_mode` and
+//! `set_mode`. A rule starting with a list of modes such as `<STR, COMMENT>`
+//! is active in those, and `<*>` in all of them: `<STR><<EOF>>` matches the
+//! end of the input in `STR`. Other rules are active in `INITIAL` and the
+//! inclusive modes. A pattern matching a '<' must therefore quote it.
+//!
+//! An action is a block evaluating to an `Option<Token>`: the token to return
+//! for the matched text, which it can read as `text`, or `None` to skip the
+//! text. An empty action skips the text.
+
+mod error;
+
+pub use error::{SpecError, SpecErrorKind};
+
+use crate::fsa::{LexerDfa, LexerNfa};
+use crate::regex::{parse, Definitions, RegexError, Span};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    pub pattern: String,
+    /// The span of the pattern in the spec.
+    pub span: Span,
+}
+
+/// What a rule does with the text its pattern matches.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Returns a token of this name.
+    Token(String),
+    /// Runs this code, without the enclosing braces.
+    Code(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub pattern: String,
+    pub action: Action,
+    /// The span of the pattern in the spec.
+    pub span: Span,
+    /// The indices into `Spec::modes` of the modes the rule is active in.
+    pub modes: Vec<usize>,
+}
+
+/// How the generated lexer runs its automaton.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// Looks up transitions in tables indexed by state and char class.
+    #[default]
+    Table,
+    /// Matches on the state and then the char.
+    Match,
+}
+
+/// A parsed lexer specification.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Spec {
+    pub engine: Engine,
+    /// The names of the modes, starting with `INITIAL`.
+    pub modes: Vec<String>,
+    pub definitions: Vec<Definition>,
+    /// The rules, in priority order.
+    pub rules: Vec<Rule>,
+    /// Everything after the second `%%` line, verbatim.
+    pub user_code: Option<String>,
+}
+
+fn is_separator(line: &str) -> bool {
+    line.trim() == "%%"
+}
+
+fn is_blank_or_comment(line: &str) -> bool {
+    let line = line.trim_start();
+
+    line.is_empty() || line.starts_with("//")
+}
+
+/// Whether `name` is a Rust keyword, or `_`, which cannot name an enum
+/// variant in the generated code.
+pub fn is_keyword(name: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "_", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+        "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if",
+        "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+        "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+    ];
+
+    KEYWORDS.contains(&name)
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => chars.all(|c| c.is_alphanumeric() || c == '_'),
+        _ => false,
+    }
+}
+
+/// Byte index of the first `=>` in `line` that is not inside a quoted text
+/// or a class.
+fn find_arrow(line: &str) -> Option<usize> {
+    let mut close = None;
+    let mut escaped = false;
+
+    for (index, c) in line.char_indices() {
+        match (close, c) {
+            _ if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(close_char), c) if c == close_char => close = None,
+            (Some(_), _) => {}
+            (None, '"') => close = Some('"'),
+            (None, '[') => close = Some(']'),
+            (None, '=') if line[index..].starts_with("=>") => return Some(index),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Byte index of the '}' matching the '{' that `code` starts with.
+///
+/// Braces inside string literals, raw string literals, char literals and
+/// comments of the code are skipped.
+fn find_closing_brace(code: &str) -> Option<usize> {
+    let chars: Vec<(usize, char)> = code.char_indices().collect();
+    let char_at = |index: usize| chars.get(index).map(|&(_, c)| c);
+    let char_before = |index: usize| index.checked_sub(1).and_then(char_at);
+    let is_ident = |c: Option<char>| c.map_or(false, |c| c.is_alphanumeric() || c == '_');
+    let mut depth = 0;
+    let mut index = 0;
+
+    while index < chars.len() {
+        match chars[index].1 {
+            // `r"..."`, `r#"..."#` and their byte string forms, which end at
+            // a '"' followed by as many '#' as they start with.
+            'r' if !is_ident(char_before(index))
+                || (char_before(index) == Some('b') && !is_ident(char_before(index - 1))) =>
+            {
+                let hashes = chars[index + 1..]
+                    .iter()
+                    .take_while(|&&(_, c)| c == '#')
+                    .count();
+
+                if char_at(index + 1 + hashes) == Some('"') {
+                    index += hashes + 2;
+
+                    while index < chars.len()
+                        && !(chars[index].1 == '"'
+                            && (1..=hashes).all(|offset| char_at(index + offset) == Some('#')))
+                    {
+                        index += 1;
+                    }
+
+                    index += hashes;
+                }
+            }
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(chars[index].0);
+                }
+            }
+            '"' => {
+                index += 1;
+
+                while index < chars.len() && chars[index].1 != '"' {
+                    if chars[index].1 == '\\' {
+                        index += 1;
+                    }
+
+                    index += 1;
+                }
+            }
+            // A char literal, as opposed to a lifetime, closes within a
+            // couple of chars.
+            '\'' => match chars.get(index + 1..index + 3) {
+                Some([(_, '\\'), _]) => {
+                    index += 3;
+
+                    while index < chars.len() && chars[index].1 != '\'' {
+                        index += 1;
+                    }
+                }
+                Some([_, (_, '\'')]) => index += 2,
+                _ => {}
+            },
+            '/' if char_at(index + 1) == Some('/') => {
+                while index < chars.len() && chars[index].1 != '\n' {
+                    index += 1;
+                }
+            }
+            // Block comments nest.
+            '/' if char_at(index + 1) == Some('*') => {
+                let mut comments = 0;
+
+                while index < chars.len() {
+                    match (chars[index].1, char_at(index + 1)) {
+                        ('/', Some('*')) => {
+                            comments += 1;
+                            index += 1;
+                        }
+                        ('*', Some('/')) => {
+                            comments -= 1;
+                            index += 1;
+
+                            if comments == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+
+                    index += 1;
+                }
+            }
+            _ => {}
+        }
+
+        index += 1;
+    }
+
+    None
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    // Byte offset of the next line to read.
+    offset: usize,
+    definitions: Definitions,
+    // The modes that rules without a list of modes are active in.
+    default_modes: Vec<usize>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, span: Span, kind: SpecErrorKind) -> SpecError {
+        SpecError::new(self.source, span, kind)
+    }
+
+    /// Reports a regex error in the pattern starting at byte `offset`.
+    fn regex_error(&self, offset: usize, error: RegexError) -> SpecError {
+        let span = error.span();
+        let span = Span::new(offset + span.start, offset + span.end);
+
+        self.error(span, SpecErrorKind::Regex(error))
+    }
+
+    /// The byte offset and contents, without the line break, of the next
+    /// line.
+    fn next_line(&mut self) -> Option<(usize, &'a str)> {
+        if self.offset >= self.source.len() {
+            return None;
+        }
+
+        let start = self.offset;
+        let rest = &self.source[start..];
+
+        let (line, length) = match rest.find('\n') {
+            Some(index) => (&rest[..index], index + 1),
+            None => (rest, rest.len()),
+        };
+
+        self.offset += length;
+
+        Some((start, line.strip_suffix('\r').unwrap_or(line)))
+    }
+
+    /// Applies the directive on `line`, which starts with '%', to `spec`.
+    fn directive(&mut self, start: usize, line: &str, spec: &mut Spec) -> Result<(), SpecError> {
+        let indent = line.len() - line.trim_start().len();
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap();
+        let name_span = Span::new(start + indent, start + indent + name.len());
+
+        match name {
+            "%option" => {}
+            "%x" | "%s" => return self.modes(line, start, name_span, name == "%s", spec),
+            _ => {
+                return Err(
+                    self.error(name_span, SpecErrorKind::UnknownDirective(name.to_string()))
+                );
+            }
+        }
+
+        let values: Vec<&str> = words.collect();
+        let value = values.join(" ");
+        let rest = &line[name_span.end - start..];
+        let value_start = match values.first() {
+            Some(_) => name_span.end + rest.len() - rest.trim_start().len(),
+            None => name_span.end,
+        };
+        let value_span = Span::new(value_start, start + line.trim_end().len());
+
+        spec.engine = match value.as_str() {
+            "table" => Engine::Table,
+            "match" => Engine::Match,
+            _ => return Err(self.error(value_span, SpecErrorKind::UnknownOption(value))),
+        };
+
+        Ok(())
+    }
+
+    /// Declares the modes named on `line` after the directive at `name_span`.
+    fn modes(
+        &mut self,
+        line: &str,
+        start: usize,
+        name_span: Span,
+        inclusive: bool,
+        spec: &mut Spec,
+    ) -> Result<(), SpecError> {
+        let mut rest = &line[name_span.end - start..];
+        let mut offset = name_span.end;
+
+        if rest.trim().is_empty() {
+            return Err(self.error(Span::new(offset, offset), SpecErrorKind::InvalidName));
+        }
+
+        while !rest.trim().is_empty() {
+            let indent = rest.len() - rest.trim_start().len();
+            let length = rest[indent..]
+                .find(|c: char| c.is_whitespace())
+                .unwrap_or(rest.len() - indent);
+            let mode = &rest[indent..indent + length];
+            let span = Span::new(offset + indent, offset + indent + length);
+
+            if !is_name(mode) {
+                return Err(self.error(span, SpecErrorKind::InvalidName));
+            }
+
+            if spec.modes.iter().any(|name| name == mode) {
+                return Err(self.error(span, SpecErrorKind::DuplicateMode(mode.to_string())));
+            }
+
+            if inclusive {
+                self.default_modes.push(spec.modes.len());
+            }
+
+            spec.modes.push(mode.to_string());
+
+            rest = &rest[indent + length..];
+            offset = span.end;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the list of modes that `line` starts with, if any, and returns
+    /// the modes with the length of the list.
+    fn rule_modes(
+        &self,
+        start: usize,
+        line: &str,
+        modes: &[String],
+    ) -> Result<(Vec<usize>, usize), SpecError> {
+        if !line.starts_with('<') || line.starts_with("<<") {
+            return Ok((self.default_modes.clone(), 0));
+        }
+
+        let arrow = find_arrow(line).unwrap_or(line.len());
+
+        let close = match line[..arrow].find('>') {
+            Some(close) => close,
+            None => {
+                return Err(self.error(
+                    Span::new(start, start + 1),
+                    SpecErrorKind::UnterminatedModes,
+                ));
+            }
+        };
+
+        if line[1..close].trim() == "*" {
+            return Ok(((0..modes.len()).collect(), close + 1));
+        }
+
+        let mut indices = vec![];
+        let mut offset = 1;
+
+        for name in line[1..close].split(',') {
+            let indent = name.len() - name.trim_start().len();
+            let span = Span::new(
+                start + offset + indent,
+                start + offset + indent + name.trim().len(),
+            );
+
+            match modes.iter().position(|mode| mode == name.trim()) {
+                Some(index) => indices.push(index),
+                None => {
+                    return Err(
+                        self.error(span, SpecErrorKind::UnknownMode(name.trim().to_string()))
+                    )
+                }
+            }
+
+            offset += name.len() + 1;
+        }
+
+        indices.sort_unstable();
+        indices.dedup();
+
+        Ok((indices, close + 1))
+    }
+
+    fn definition(&mut self, start: usize, line: &str) -> Result<Definition, SpecError> {
+        let indent = line.len() - line.trim_start().len();
+        let rest = &line[indent..];
+
+        let name_length = rest.find(|c: char| c.is_whitespace()).unwrap_or(rest.len());
+        let name = &rest[..name_length];
+        let name_span = Span::new(start + indent, start + indent + name_length);
+
+        if !is_name(name) {
+            return Err(self.error(name_span, SpecErrorKind::InvalidName));
+        }
+
+        if self.definitions.contains(name) {
+            return Err(self.error(
+                name_span,
+                SpecErrorKind::DuplicateDefinition(name.to_string()),
+            ));
+        }
+
+        let after = &rest[name_length..];
+        let pattern = after.trim();
+        let pattern_start = name_span.end + (after.len() - after.trim_start().len());
+
+        if pattern.is_empty() {
+            let end = start + line.len();
+
+            return Err(self.error(Span::new(end, end), SpecErrorKind::MissingPattern));
+        }
+
+        self.definitions
+            .define(name, pattern)
+            .map_err(|error| self.regex_error(pattern_start, error))?;
+
+        Ok(Definition {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            span: Span::new(pattern_start, pattern_start + pattern.len()),
+        })
+    }
+
+    fn rule(&mut self, start: usize, line: &str, modes: &[String]) -> Result<Rule, SpecError> {
+        let end = start + line.trim_end().len();
+        let indent = line.len() - line.trim_start().len();
+        let (rule_modes, length) = self.rule_modes(start + indent, &line[indent..], modes)?;
+        let indent = indent + length;
+        let indent = indent + (line[indent..].len() - line[indent..].trim_start().len());
+
+        let arrow = match find_arrow(line) {
+            Some(arrow) => arrow,
+            None => {
+                return Err(
+                    self.error(Span::new(start + indent, end), SpecErrorKind::MissingAction)
+                );
+            }
+        };
+
+        let pattern = line[indent..arrow].trim();
+        let pattern_start = start + indent;
+        let arrow_span = Span::new(start + arrow, start + arrow + 2);
+
+        if pattern.is_empty() {
+            return Err(self.error(arrow_span, SpecErrorKind::MissingPattern));
+        }
+
+        parse(pattern)
+            .and_then(|ast| self.definitions.resolve(&ast))
+            .map_err(|error| self.regex_error(pattern_start, error))?;
+
+        let after = &line[arrow + 2..];
+        let action_start = arrow_span.end + (after.len() - after.trim_start().len());
+        let after = after.trim();
+
+        let action = if after.starts_with('{') {
+            self.code(action_start)?
+        } else if after.is_empty() {
+            return Err(self.error(arrow_span, SpecErrorKind::MissingAction));
+        } else if is_keyword(after) {
+            return Err(self.error(
+                Span::new(action_start, action_start + after.len()),
+                SpecErrorKind::KeywordTokenName(after.to_string()),
+            ));
+        } else if is_name(after) {
+            Action::Token(after.to_string())
+        } else {
+            return Err(self.error(
+                Span::new(action_start, end),
+                SpecErrorKind::InvalidTokenName(after.to_string()),
+            ));
+        };
+
+        Ok(Rule {
+            pattern: pattern.to_string(),
+            action,
+            span: Span::new(pattern_start, pattern_start + pattern.len()),
+            modes: rule_modes,
+        })
+    }
+
+    /// Reads the action starting with the '{' at byte `start`, which may
+    /// continue on the following lines.
+    fn code(&mut self, start: usize) -> Result<Action, SpecError> {
+        let close = match find_closing_brace(&self.source[start..]) {
+            Some(close) => start + close,
+            None => {
+                return Err(self.error(
+                    Span::new(start, start + 1),
+                    SpecErrorKind::UnterminatedAction,
+                ));
+            }
+        };
+
+        // Continue after the line the action ends on, which must not hold
+        // anything else.
+        self.offset = close;
+
+        let (_, rest) = self.next_line().unwrap();
+        let trailing = rest[1..].trim();
+
+        if !trailing.is_empty() {
+            let trailing_start = close + 1 + (rest[1..].len() - rest[1..].trim_start().len());
+
+            return Err(self.error(
+                Span::new(trailing_start, trailing_start + trailing.len()),
+                SpecErrorKind::TrailingText,
+            ));
+        }
+
+        Ok(Action::Code(
+            self.source[start + 1..close].trim().to_string(),
+        ))
+    }
+}
+
+impl Spec {
+    /// Parses the source of a spec, checking every pattern on the way.
+    ///
+    /// References in patterns are resolved against the definitions of the
+    /// spec, and names that are not defined are matched as literal text.
+    pub fn parse(source: &str) -> Result<Spec, SpecError> {
+        let mut parser = Parser {
+            source,
+            offset: 0,
+            definitions: Definitions::new(),
+            default_modes: vec![0],
+        };
+        let mut spec = Spec {
+            modes: vec!["INITIAL".to_string()],
+            ..Spec::default()
+        };
+
+        loop {
+            let (start, line) = match parser.next_line() {
+                Some(line) => line,
+                None => {
+                    let end = Span::new(source.len(), source.len());
+
+                    return Err(parser.error(end, SpecErrorKind::MissingRulesSection));
+                }
+            };
+
+            if is_separator(line) {
+                break;
+            }
+
+            if line.trim_start().starts_with('%') {
+                parser.directive(start, line, &mut spec)?;
+            } else if !is_blank_or_comment(line) {
+                spec.definitions.push(parser.definition(start, line)?);
+            }
+        }
+
+        while let Some((start, line)) = parser.next_line() {
+            if is_separator(line) {
+                spec.user_code = Some(source[parser.offset..].to_string());
+                break;
+            }
+
+            if !is_blank_or_comment(line) {
+                let rule = parser.rule(start, line, &spec.modes)?;
+
+                spec.rules.push(rule);
+            }
+        }
+
+        if spec.rules.is_empty() {
+            let end = Span::new(parser.offset, parser.offset);
+
+            return Err(parser.error(end, SpecErrorKind::NoRules));
+        }
+
+        Ok(spec)
+    }
+
+    /// Builds the minimized automaton of the rules, in which every accepting
+    /// state maps to the index of the rule that wins it.
+    ///
+    /// `source` is the text the spec was parsed from, and is only used for
+    /// error positions.
+    pub fn build_dfa(&self, source: &str) -> Result<LexerDfa, SpecError> {
+        let regex_error = |offset: usize, error: RegexError| {
+            let span = error.span();
+            let span = Span::new(offset + span.start, offset + span.end);
+
+            SpecError::new(source, span, SpecErrorKind::Regex(error))
+        };
+
+        let mut definitions = Definitions::new();
+
+        for definition in &self.definitions {
+            definitions
+                .define(&definition.name, &definition.pattern)
+                .map_err(|error| regex_error(definition.span.start, error))?;
+        }
+
+        let rules: Vec<(&str, &str)> = self
+            .rules
+            .iter()
+            .map(|rule| match &rule.action {
+                Action::Token(name) => (name.as_str(), rule.pattern.as_str()),
+                Action::Code(_) => ("", rule.pattern.as_str()),
+            })
+            .collect();
+
+        let modes: Vec<Vec<usize>> = self.rules.iter().map(|rule| rule.modes.clone()).collect();
+
+        let nfa =
+            LexerNfa::from_rules_in_modes(&rules, &modes, self.modes.len().max(1), &definitions)
+                .map_err(|error| regex_error(self.rules[error.rule].span.start, error.error))?;
+
+        let mut dfa = LexerDfa::from_nfa(&nfa);
+        dfa.minimize();
+
+        Ok(dfa)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(name: &str) -> Action {
+        Action::Token(name.to_string())
+    }
+
+    fn error_of(source: &str) -> (usize, usize, SpecErrorKind) {
+        let error = Spec::parse(source).unwrap_err();
+
+        (error.line, error.column, error.kind)
+    }
+
+    #[test]
+    fn sections() {
+        let source =
+            "digit 0-9\nident  a-z+\n%%\nident => Ident\ndigit+ => Number\n%%\nfn main() {}\n";
+        let spec = Spec::parse(source).unwrap();
+
+        assert_eq!(
+            spec.definitions
+                .iter()
+                .map(|definition| (definition.name.as_str(), definition.pattern.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("digit", "0-9"), ("ident", "a-z+")]
+        );
+        assert_eq!(
+            spec.rules
+                .iter()
+                .map(|rule| (rule.pattern.as_str(), rule.action.clone()))
+                .collect::<Vec<_>>(),
+            vec![("ident", token("Ident")), ("digit+", token("Number"))]
+        );
+        assert_eq!(spec.user_code.as_deref(), Some("fn main() {}\n"));
+        assert_eq!(spec.definitions[1].span, Span::new(17, 21));
+        assert_eq!(spec.rules[1].span, Span::new(40, 46));
+    }
+
+    #[test]
+    fn user_code_is_optional() {
+        let spec = Spec::parse("%%\na => A\n").unwrap();
+
+        assert!(spec.definitions.is_empty());
+        assert_eq!(spec.user_code, None);
+    }
+
+    #[test]
+    fn comments_and_blank_lines() {
+        let source = "// digits\ndigit 0-9\n\n%%\n  // keywords\n\"if\" => If\n\n";
+        let spec = Spec::parse(source).unwrap();
+
+        assert_eq!(spec.definitions.len(), 1);
+        assert_eq!(spec.rules.len(), 1);
+    }
+
+    #[test]
+    fn arrows_in_patterns() {
+        let spec = Spec::parse("%%\n\"=>\" => Arrow\n[=>] => Angle\n").unwrap();
+
+        assert_eq!(spec.rules[0].pattern, "\"=>\"");
+        assert_eq!(spec.rules[1].pattern, "[=>]");
+        assert_eq!(spec.rules[1].action, token("Angle"));
+    }
+
+    #[test]
+    fn actions() {
+        let source = "%%\n\" \" => { }\n\"\\n\" => {\n    self.line += 1;\n    if true { None } else { Some('}') }\n}\na => A\n";
+        let spec = Spec::parse(source).unwrap();
+
+        assert_eq!(spec.rules[0].action, Action::Code(String::new()));
+        assert_eq!(
+            spec.rules[1].action,
+            Action::Code("self.line += 1;\n    if true { None } else { Some('}') }".to_string())
+        );
+        assert_eq!(spec.rules[2].action, token("A"));
+    }
+
+    #[test]
+    fn closing_brace_skips_strings_and_comments() {
+        assert_eq!(find_closing_brace(r#"{ "}" }"#), Some(6));
+        assert_eq!(find_closing_brace("{ '}' }"), Some(6));
+        assert_eq!(find_closing_brace("{ '\\'' }"), Some(7));
+        assert_eq!(find_closing_brace("{ // }\n}"), Some(7));
+        assert_eq!(find_closing_brace("{ x: &'a str }"), Some(13));
+        assert_eq!(find_closing_brace("{ {}"), None);
+    }
+
+    #[test]
+    fn closing_brace_skips_block_comments_and_raw_strings() {
+        assert_eq!(find_closing_brace("{ /* } */ }"), Some(10));
+        assert_eq!(find_closing_brace("{ /* /* } */ } */ }"), Some(18));
+        assert_eq!(find_closing_brace("{ /*/ } */ }"), Some(11));
+        assert_eq!(find_closing_brace(r##"{ r"}" }"##), Some(7));
+        assert_eq!(find_closing_brace(r###"{ r#"}"# }"###), Some(9));
+        assert_eq!(find_closing_brace(r###"{ r#" "} "# }"###), Some(12));
+        assert_eq!(find_closing_brace(r###"{ br#"}"# }"###), Some(10));
+        assert_eq!(find_closing_brace("{ for r in x {} }"), Some(16));
+        assert_eq!(find_closing_brace("{ bar }"), Some(6));
+        assert_eq!(find_closing_brace("{ /* }"), None);
+
+        let source = "%%\na => { r#\"}\"#; /* } */ None }\n";
+
+        assert_eq!(
+            Spec::parse(source).unwrap().rules[0].action,
+            Action::Code("r#\"}\"#; /* } */ None".to_string())
+        );
+    }
+
+    #[test]
+    fn structure_errors() {
+        assert_eq!(
+            error_of("digit 0-9\n"),
+            (2, 1, SpecErrorKind::MissingRulesSection)
+        );
+        assert_eq!(error_of("%%\n\n"), (3, 1, SpecErrorKind::NoRules));
+        assert_eq!(error_of("9x 0-9\n%%\n"), (1, 1, SpecErrorKind::InvalidName));
+        assert_eq!(
+            error_of("d 0-9\nd 0-9\n%%\n"),
+            (2, 1, SpecErrorKind::DuplicateDefinition("d".to_string()))
+        );
+        assert_eq!(error_of("d\n%%\n"), (1, 2, SpecErrorKind::MissingPattern));
+    }
+
+    #[test]
+    fn rule_errors() {
+        assert_eq!(
+            error_of("%%\n  a-z\n"),
+            (2, 3, SpecErrorKind::MissingAction)
+        );
+        assert_eq!(error_of("%%\na =>\n"), (2, 3, SpecErrorKind::MissingAction));
+        assert_eq!(
+            error_of("%%\n => A\n"),
+            (2, 2, SpecErrorKind::MissingPattern)
+        );
+        assert_eq!(
+            error_of("%%\na => A B\n"),
+            (2, 6, SpecErrorKind::InvalidTokenName("A B".to_string()))
+        );
+
+        for keyword in &["fn", "Self", "match", "_"] {
+            let error = Spec::parse(&format!("%%\na =>  {} \n", keyword)).unwrap_err();
+
+            assert_eq!(
+                error.kind,
+                SpecErrorKind::KeywordTokenName(keyword.to_string())
+            );
+            assert_eq!(error.span, Span::new(9, 9 + keyword.len()));
+        }
+        assert_eq!(
+            error_of("%%\na => {\n"),
+            (2, 6, SpecErrorKind::UnterminatedAction)
+        );
+        assert_eq!(
+            error_of("%%\na => {\n} A\n"),
+            (3, 3, SpecErrorKind::TrailingText)
+        );
+    }
+
+    #[test]
+    fn regex_errors_point_into_the_spec() {
+        let (line, column, kind) = error_of("d  0-9 |\n%%\n");
+
+        assert_eq!((line, column), (1, 8));
+        assert!(matches!(
+            kind,
+            SpecErrorKind::Regex(RegexError::DanglingOperator(_))
+        ));
+
+        let (line, column, kind) = error_of("%%\n  \"é\" # => A\n");
+
+        assert_eq!((line, column), (2, 7));
+        assert_eq!(
+            kind,
+            SpecErrorKind::Regex(RegexError::UnsupportedCharacter('#', Span::new(5, 6)))
+        );
+
+        let source = "%%\nx => X\n a+ / b* => A\n";
+        let error = Spec::parse(source).unwrap().build_dfa(source).unwrap_err();
+
+        assert_eq!((error.line, error.column), (3, 5));
+        assert!(matches!(
+            error.kind,
+            SpecErrorKind::Regex(RegexError::VariableTrailingContext(_))
+        ));
+
+        let source = "%%\n\"aa\"{18446744073709551615} / \"b\" => A\n";
+        let error = Spec::parse(source).unwrap().build_dfa(source).unwrap_err();
+
+        assert!(matches!(
+            error.kind,
+            SpecErrorKind::Regex(RegexError::RepeatTooLarge(_, _))
+        ));
+    }
+
+    #[test]
+    fn references_are_resolved() {
+        let (line, column, kind) = error_of("list item list\nitem a\n%%\nx list => L\n");
+
+        assert_eq!((line, column), (4, 3));
+        assert!(matches!(
+            kind,
+            SpecErrorKind::Regex(RegexError::RecursiveReference(_, _))
+        ));
+    }
+
+    #[test]
+    fn diagnostic() {
+        let source = "%%\na | => A\n";
+        let error = Spec::parse(source).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "2:3: operator is missing its right operand"
+        );
+        assert_eq!(
+            error.diagnostic(source).to_string(),
+            "error: operator is missing its right operand\n --> 2:3\n  |\n2 | a | => A\n  |   ^"
+        );
+    }
+
+    #[test]
+    fn options() {
+        assert_eq!(Spec::parse("%%\na => A\n").unwrap().engine, Engine::Table);
+        assert_eq!(
+            Spec::parse("%option match\n%%\na => A\n").unwrap().engine,
+            Engine::Match
+        );
+        assert_eq!(
+            Spec::parse("%option match\n %option table\n%%\na => A\n")
+                .unwrap()
+                .engine,
+            Engine::Table
+        );
+        assert_eq!(
+            error_of("%option fast\n%%\na => A\n"),
+            (1, 9, SpecErrorKind::UnknownOption("fast".to_string()))
+        );
+        assert_eq!(
+            error_of(" %option  option\n%%\na => A\n"),
+            (1, 11, SpecErrorKind::UnknownOption("option".to_string()))
+        );
+        assert_eq!(
+            error_of("%option\n%%\na => A\n"),
+            (1, 8, SpecErrorKind::UnknownOption(String::new()))
+        );
+        assert_eq!(
+            error_of("%define x\n%%\na => A\n"),
+            (1, 1, SpecErrorKind::UnknownDirective("%define".to_string()))
+        );
+    }
+
+    #[test]
+    fn modes() {
+        let source = "%x STR\n%s COMMENT\n%%\n\"a\" => A\n<STR> \"b\" => B\n<STR, INITIAL> c => C\n<*> d => D\n";
+        let spec = Spec::parse(source).unwrap();
+
+        assert_eq!(spec.modes, vec!["INITIAL", "STR", "COMMENT"]);
+
+        let modes: Vec<&[usize]> = spec.rules.iter().map(|rule| &rule.modes[..]).collect();
+
+        assert_eq!(modes, vec![&[0, 2][..], &[1], &[0, 1], &[0, 1, 2]]);
+        assert_eq!(spec.rules[1].pattern, "\"b\"");
+        assert_eq!(&source[spec.rules[1].span.start..][..3], "\"b\"");
+
+        let dfa = spec.build_dfa(source).unwrap();
+
+        assert_eq!(dfa.starts().len(), 3);
+
+        assert_eq!(Spec::parse("%%\na => A\n").unwrap().modes, vec!["INITIAL"]);
+        assert_eq!(
+            error_of("%x A\n%s B A\n%%\na => A\n"),
+            (2, 6, SpecErrorKind::DuplicateMode("A".to_string()))
+        );
+        assert_eq!(
+            error_of("%x INITIAL\n%%\na => A\n"),
+            (1, 4, SpecErrorKind::DuplicateMode("INITIAL".to_string()))
+        );
+        assert_eq!(
+            error_of("%x\n%%\na => A\n"),
+            (1, 3, SpecErrorKind::InvalidName)
+        );
+        assert_eq!(
+            error_of("%x A\n%%\n<A, B> a => A\n"),
+            (3, 5, SpecErrorKind::UnknownMode("B".to_string()))
+        );
+        assert_eq!(
+            error_of("%%\n <INITIAL a => A\n"),
+            (2, 2, SpecErrorKind::UnterminatedModes)
+        );
+    }
+
+    #[test]
+    fn crlf_line_breaks() {
+        let spec = Spec::parse("d 0-9\r\n%%\r\nd+ => Number\r\n").unwrap();
+
+        assert_eq!(spec.definitions[0].pattern, "0-9");
+        assert_eq!(spec.rules[0].action, token("Number"));
+    }
+
+    #[test]
+    fn anchors() {
+        let source = "%x STR\n%%\n^ \"#\" => Hash\n<<EOF>> => Eof\n<STR><<EOF>> => { None }\n";
+        let spec = Spec::parse(source).unwrap();
+
+        assert_eq!(spec.rules[1].pattern, "<<EOF>>");
+        assert_eq!(spec.rules[1].modes, vec![0]);
+        assert_eq!(spec.rules[2].pattern, "<<EOF>>");
+        assert_eq!(spec.rules[2].modes, vec![1]);
+
+        let dfa = spec.build_dfa(source).unwrap();
+
+        assert_eq!(dfa.end_rule(0), Some(1));
+        assert_eq!(dfa.end_rule(1), Some(2));
+
+        let source = "%%\na ^ => A\n";
+        let error = Spec::parse(source).unwrap().build_dfa(source).unwrap_err();
+
+        assert_eq!((error.line, error.column), (2, 3));
+        assert_eq!(
+            error.kind,
+            SpecErrorKind::Regex(RegexError::MisplacedAnchor(Span::new(2, 3)))
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn rlex(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rlex"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// A file in the temp dir, named after this test run, that is removed when
+/// dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, contents: &str) -> Self {
+        let path = env::temp_dir().join(format!("rlex-cli-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+
+        TempFile(path)
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn generates_to_stdout_and_file() {
+    let spec = TempFile::new("ok.rlex", "%%\na-z+ => Word\n");
+
+    let output = rlex(&[spec.path()]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("pub enum Token"));
+
+    let out = TempFile::new("ok.rs", "");
+    let output = rlex(&[spec.path(), "-o", out.path(), "--engine", "match"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(fs::read_to_string(out.path())
+        .unwrap()
+        .contains("match ch {"));
+}
+
+#[test]
+fn check_only_validates() {
+    let spec = TempFile::new("check.rlex", "%%\na-z+ => Word\n");
+    let out = TempFile::new("check.rs", "");
+    let output = rlex(&["--check", spec.path(), "-o", out.path()]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(fs::read_to_string(out.path()).unwrap().is_empty());
+}
+
+#[test]
+fn errors_are_rendered() {
+    let spec = TempFile::new("bad.rlex", "%%\na | => A\n");
+    let output = rlex(&["--check", spec.path()]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&format!(
+        "error: operator is missing its right operand\n --> {}:2:3",
+        spec.path()
+    )));
+
+    let output = rlex(&["--check"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = rlex(&["--check", "/nonexistent/spec.rlex"]);
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -0,0 +1,370 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use rlex::{Engine, Spec};
+
+/// Checks that the lexer generated from `tests/golden/<name>.rlex` with
+/// `engine` is the one in `tests/golden/<name>_<engine>.rs`. Set `RLEX_BLESS`
+/// to write it there instead.
+fn check_golden(name: &str, engine: Engine) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let source = fs::read_to_string(dir.join(format!("{}.rlex", name))).unwrap();
+
+    let mut spec = Spec::parse(&source).unwrap();
+    spec.engine = engine;
+
+    let dfa = spec.build_dfa(&source).unwrap();
+    let generated = rlex::codegen::generate(&spec, &dfa);
+
+    let suffix = match engine {
+        Engine::Table => "table",
+        Engine::Match => "match",
+    };
+    let path = dir.join(format!("{}_{}.rs", name, suffix));
+
+    if env::var_os("RLEX_BLESS").is_some() {
+        fs::write(&path, &generated).unwrap();
+    }
+
+    let golden = fs::read_to_string(&path).unwrap();
+
+    assert!(
+        generated == golden,
+        "{} is out of date, rerun with RLEX_BLESS=1",
+        path.display()
+    );
+}
+
+/// The lexers generated from `tests/golden/<name>.rlex` by both engines, in
+/// the modules `table` and `direct` of a module `<name>`, each with the tests
+/// that `$tests` expands to. The module also checks that both are up to date
+/// and lex every input of `$inputs` alike.
+macro_rules! golden_lexers {
+    ($name:ident, $tests:ident, $inputs:expr) => {
+        mod $name {
+            #[deny(warnings)]
+            mod table {
+                include!(concat!("golden/", stringify!($name), "_table.rs"));
+
+                $tests!();
+            }
+
+            #[deny(warnings)]
+            mod direct {
+                include!(concat!("golden/", stringify!($name), "_match.rs"));
+
+                $tests!();
+            }
+
+            #[test]
+            fn golden() {
+                super::check_golden(stringify!($name), rlex::Engine::Table);
+                super::check_golden(stringify!($name), rlex::Engine::Match);
+            }
+
+            #[test]
+            fn engines_lex_alike() {
+                for input in $inputs.iter() {
+                    let table: Vec<String> = table::Lexer::new(input)
+                        .map(|lexeme| format!("{:?}", lexeme))
+                        .collect();
+                    let direct: Vec<String> = direct::Lexer::new(input)
+                        .map(|lexeme| format!("{:?}", lexeme))
+                        .collect();
+
+                    assert_eq!(table, direct, "lexing {:?}", input);
+                }
+            }
+        }
+    };
+}
+
+macro_rules! calc_tests {
+    () => {
+        #[test]
+        fn lexes() {
+            let input = "let x = 1.5 ** (y2 - 3) // done\n/ 4";
+            let lexemes: Vec<(Token, &str)> = Lexer::new(input)
+                .map(|lexeme| {
+                    let lexeme = lexeme.unwrap();
+
+                    assert_eq!(&input[lexeme.start..lexeme.end], lexeme.text);
+
+                    (lexeme.token, lexeme.text)
+                })
+                .collect();
+
+            assert_eq!(
+                lexemes,
+                vec![
+                    (Token::Let, "let"),
+                    (Token::Ident, "x"),
+                    (Token::Eq, "="),
+                    (Token::Number, "1.5"),
+                    (Token::Star, "**"),
+                    (Token::LParen, "("),
+                    (Token::Ident, "y2"),
+                    (Token::Minus, "-"),
+                    (Token::Number, "3"),
+                    (Token::RParen, ")"),
+                    (Token::Slash, "/"),
+                    (Token::Number, "4"),
+                ]
+            );
+            assert!(lexemes.iter().any(|&(token, _)| token.is_operator()));
+            assert!(!Token::Number.is_operator());
+        }
+
+        #[test]
+        fn errors() {
+            let mut lexer = Lexer::new("a # b");
+
+            assert_eq!(lexer.next().unwrap().unwrap().token, Token::Ident);
+            assert_eq!(lexer.next(), Some(Err(LexError { offset: 2, ch: '#' })));
+            assert_eq!(lexer.offset(), 3);
+            assert_eq!(lexer.next().unwrap().unwrap().text, "b");
+            assert_eq!(lexer.next(), None);
+        }
+    };
+}
+
+macro_rules! greek_tests {
+    () => {
+        #[test]
+        fn lexes_non_ascii() {
+            let mut lexer = Lexer::new("Ωμέγα άλφα x");
+            let lexemes: Vec<Result<Lexeme, LexError>> = lexer.by_ref().collect();
+
+            assert_eq!(
+                lexemes,
+                vec![
+                    Ok(Lexeme {
+                        token: Token::Name,
+                        text: "Ωμ",
+                        start: 0,
+                        end: 4
+                    }),
+                    Err(LexError {
+                        offset: 4, ch: 'έ'
+                    }),
+                    Ok(Lexeme {
+                        token: Token::Word,
+                        text: "γα",
+                        start: 6,
+                        end: 10
+                    }),
+                    Err(LexError {
+                        offset: 11,
+                        ch: 'ά'
+                    }),
+                    Ok(Lexeme {
+                        token: Token::Word,
+                        text: "λφα",
+                        start: 13,
+                        end: 19
+                    }),
+                    Err(LexError {
+                        offset: 20,
+                        ch: 'x'
+                    }),
+                ]
+            );
+            assert_eq!(lexer.offset(), 21);
+        }
+    };
+}
+
+macro_rules! strings_tests {
+    () => {
+        #[test]
+        fn switches_modes() {
+            let mut lexer = Lexer::new("a \"b\\\"c\" /* x /* \"y */ */ d // e\nf \"g");
+            let mut lexemes = vec![];
+
+            while let Some(lexeme) = lexer.next() {
+                let lexeme = lexeme.unwrap();
+
+                lexemes.push((lexeme.token, lexeme.text, lexer.mode()));
+            }
+
+            assert_eq!(
+                lexemes,
+                vec![
+                    (Token::Ident, "a", Mode::Initial),
+                    (Token::Text, "b", Mode::Str),
+                    (Token::Escape, "\\\"", Mode::Str),
+                    (Token::Text, "c", Mode::Str),
+                    (Token::Ident, "d", Mode::Initial),
+                    (Token::Ident, "f", Mode::Initial),
+                    (Token::Text, "g", Mode::Str),
+                ]
+            );
+        }
+
+        #[test]
+        fn mode_stack() {
+            let mut lexer = Lexer::new("");
+
+            assert_eq!(lexer.mode(), Mode::Initial);
+            assert_eq!(lexer.pop_mode(), None);
+
+            lexer.push_mode(Mode::Str);
+            lexer.set_mode(Mode::BlockComment);
+
+            assert_eq!(lexer.mode(), Mode::BlockComment);
+            assert_eq!(lexer.pop_mode(), Some(Mode::BlockComment));
+            assert_eq!(lexer.mode(), Mode::Initial);
+        }
+
+        #[test]
+        fn rules_of_other_modes_do_not_match() {
+            let mut lexer = Lexer::new("*/ \"\n");
+
+            assert_eq!(lexer.next(), Some(Err(LexError { offset: 0, ch: '*' })));
+            assert_eq!(lexer.next(), Some(Err(LexError { offset: 1, ch: '/' })));
+            assert_eq!(
+                lexer.next(),
+                Some(Err(LexError {
+                    offset: 4,
+                    ch: '\n'
+                }))
+            );
+            assert_eq!(lexer.mode(), Mode::Str);
+            assert_eq!(lexer.offset(), 5);
+            assert_eq!(lexer.next(), None);
+        }
+    };
+}
+
+macro_rules! ranges_tests {
+    () => {
+        #[test]
+        fn trailing_context() {
+            let mut lexer = Lexer::new("1..2.5 if (x) iffy if");
+            let lexemes: Vec<(Token, &str)> = lexer
+                .by_ref()
+                .map(|lexeme| {
+                    let lexeme = lexeme.unwrap();
+
+                    (lexeme.token, lexeme.text)
+                })
+                .collect();
+
+            assert_eq!(
+                lexemes,
+                vec![
+                    (Token::Int, "1"),
+                    (Token::Range, ".."),
+                    (Token::Float, "2.5"),
+                    (Token::If, "if"),
+                    (Token::LParen, "("),
+                    (Token::Ident, "x"),
+                    (Token::RParen, ")"),
+                    (Token::Ident, "iffy"),
+                    (Token::Ident, "if"),
+                ]
+            );
+            assert_eq!(lexer.offset(), 21);
+            assert_eq!(
+                Lexer::new("1.x").nth(1),
+                Some(Err(LexError { offset: 1, ch: '.' }))
+            );
+        }
+    };
+}
+
+macro_rules! lines_tests {
+    () => {
+        #[test]
+        fn anchors() {
+            let lexemes: Vec<(Token, &str)> = Lexer::new("[main]\n# x\nname = a b\nk w\nend")
+                .map(|lexeme| {
+                    let lexeme = lexeme.unwrap();
+
+                    (lexeme.token, lexeme.text)
+                })
+                .collect();
+
+            assert_eq!(
+                lexemes,
+                vec![
+                    (Token::Section, "[main]"),
+                    (Token::Word, "name"),
+                    (Token::Value, " a b"),
+                    (Token::Word, "k"),
+                    (Token::LastWord, "w"),
+                    (Token::Word, "end"),
+                    (Token::Eof, ""),
+                ]
+            );
+            assert_eq!(
+                Lexer::new("k [v]").nth(1),
+                Some(Err(LexError { offset: 2, ch: '[' }))
+            );
+        }
+
+        #[test]
+        fn end_of_input() {
+            let mut lexer = Lexer::new("k = v");
+
+            assert_eq!(lexer.nth(1).unwrap().unwrap().token, Token::Value);
+            assert_eq!(
+                lexer.next(),
+                Some(Ok(Lexeme {
+                    token: Token::Unterminated,
+                    text: "",
+                    start: 5,
+                    end: 5
+                }))
+            );
+            assert_eq!(lexer.next(), None);
+            assert_eq!(lexer.mode(), Mode::Value);
+            assert_eq!(lexer.offset(), 5);
+
+            let mut lexer = Lexer::new("# x");
+            lexer.set_mode(Mode::Value);
+
+            assert_eq!(lexer.next().unwrap().unwrap().token, Token::Value);
+        }
+    };
+}
+
+golden_lexers!(
+    calc,
+    calc_tests,
+    [
+        "",
+        "let x = 1.5 ** (y2 - 3) // done\n/ 4",
+        "1. .5 1..2 letter let_ 3x",
+        "a\tb\n// é\n#é*/+-"
+    ]
+);
+
+golden_lexers!(
+    greek,
+    greek_tests,
+    ["", "Ωμέγα άλφα x", "ΑΒΓ  αβγ\u{10FFFF}ω"]
+);
+
+golden_lexers!(
+    strings,
+    strings_tests,
+    [
+        "a \"b\\\"c\" /* x /* \"y */ */ d",
+        "// \"e\nf \"g\nh\"",
+        "*/ \"\\",
+    ]
+);
+
+golden_lexers!(
+    ranges,
+    ranges_tests,
+    ["1..2.5 if (iffy if", "1.. ..3 if", "ifé(1"]
+);
+
+golden_lexers!(
+    lines,
+    lines_tests,
+    ["", "[a]\n# x\nk = v\n", "a #b [c]\n[d] e", "k = v", "\n\n#"]
+);
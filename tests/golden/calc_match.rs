@@ -0,0 +1,202 @@
+// Generated by rlex. Do not edit.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Let,
+    Ident,
+    Number,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Eq,
+    LParen,
+    RParen,
+}
+
+/// A token with the text it was matched from and the byte offsets of the
+/// text in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lexeme<'a> {
+    pub token: Token,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A char at which no rule matches, with its byte offset in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub offset: usize,
+    pub ch: char,
+}
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer { input, offset: 0 }
+    }
+
+    /// The byte offset at which the next lexeme starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The length and rule of the longest non-empty match at the offset.
+    fn longest_match(&self) -> Option<(usize, usize)> {
+        let mut state = 0;
+        let mut last_accept = None;
+
+        for (index, ch) in self.input[self.offset..].char_indices() {
+            state = match state {
+                0 => match ch {
+                    '\t'..='\n' | ' ' => 1,
+                    '(' => 2,
+                    ')' => 3,
+                    '*' => 4,
+                    '+' => 5,
+                    '-' => 6,
+                    '/' => 7,
+                    '0'..='9' => 8,
+                    '=' => 9,
+                    'A'..='Z' | '_' | 'a'..='k' | 'm'..='z' => 10,
+                    'l' => 11,
+                    _ => break,
+                },
+                4 => match ch {
+                    '*' => 12,
+                    _ => break,
+                },
+                7 => match ch {
+                    '/' => 13,
+                    _ => break,
+                },
+                8 => match ch {
+                    '0'..='9' => 8,
+                    '.' => 14,
+                    _ => break,
+                },
+                10 => match ch {
+                    '0'..='9' | 'A'..='Z' | '_' | 'a'..='z' => 10,
+                    _ => break,
+                },
+                11 => match ch {
+                    '0'..='9' | 'A'..='Z' | '_' | 'a'..='d' | 'f'..='z' => 10,
+                    'e' => 15,
+                    _ => break,
+                },
+                13 => match ch {
+                    '\0'..='\t' | '\u{b}'..='\u{10ffff}' => 13,
+                    _ => break,
+                },
+                14 => match ch {
+                    '0'..='9' => 16,
+                    _ => break,
+                },
+                15 => match ch {
+                    '0'..='9' | 'A'..='Z' | '_' | 'a'..='s' | 'u'..='z' => 10,
+                    't' => 17,
+                    _ => break,
+                },
+                16 => match ch {
+                    '0'..='9' => 16,
+                    _ => break,
+                },
+                17 => match ch {
+                    '0'..='9' | 'A'..='Z' | '_' | 'a'..='z' => 10,
+                    _ => break,
+                },
+                1..=3 | 5 | 6 | 9 | 12 => break,
+                _ => unreachable!(),
+            };
+
+            let rule = match state {
+                17 => 0,
+                10 | 11 | 15 => 1,
+                8 | 16 => 2,
+                5 => 3,
+                6 => 4,
+                4 => 5,
+                7 => 6,
+                9 => 7,
+                2 => 8,
+                3 => 9,
+                13 => 10,
+                1 => 11,
+                12 => 12,
+                _ => continue,
+            };
+
+            last_accept = Some((index + ch.len_utf8(), rule));
+        }
+
+        last_accept
+    }
+
+    /// The token to return for `text`, matched by `rule`, if any.
+    #[allow(unused_variables)]
+    fn action(&mut self, rule: usize, text: &'a str) -> Option<Token> {
+        match rule {
+            0 => Some(Token::Let),
+            1 => Some(Token::Ident),
+            2 => Some(Token::Number),
+            3 => Some(Token::Plus),
+            4 => Some(Token::Minus),
+            5 => Some(Token::Star),
+            6 => Some(Token::Slash),
+            7 => Some(Token::Eq),
+            8 => Some(Token::LParen),
+            9 => Some(Token::RParen),
+            10 => None,
+            11 => {
+                None
+            }
+            12 => {
+                // `**` is spelled out as two stars.
+                let _ = text;
+                Some(Token::Star)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Lexeme<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.offset;
+            let ch = self.input[start..].chars().next()?;
+
+            match self.longest_match() {
+                Some((length, rule)) => {
+                    self.offset += length;
+
+                    let text = &self.input[start..self.offset];
+
+                    if let Some(token) = self.action(rule, text) {
+                        let end = self.offset;
+
+                        return Some(Ok(Lexeme { token, text, start, end }));
+                    }
+                }
+                None => {
+                    self.offset += ch.len_utf8();
+
+                    return Some(Err(LexError { offset: start, ch }));
+                }
+            }
+        }
+    }
+}
+
+impl Token {
+    pub fn is_operator(self) -> bool {
+        matches!(self, Token::Plus | Token::Minus | Token::Star | Token::Slash)
+    }
+}
@@ -0,0 +1,223 @@
+// Generated by rlex. Do not edit.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Let,
+    Ident,
+    Number,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Eq,
+    LParen,
+    RParen,
+}
+
+/// A token with the text it was matched from and the byte offsets of the
+/// text in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lexeme<'a> {
+    pub token: Token,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A char at which no rule matches, with its byte offset in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub offset: usize,
+    pub ch: char,
+}
+
+const START: usize = 1;
+const CLASSES: usize = 17;
+
+// The class of every ASCII char.
+static ASCII_CLASSES: [u8; 128] = [
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 3, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    2, 1, 1, 1, 1, 1, 1, 1, 4, 5, 6, 7, 1, 8, 9, 10,
+    11, 11, 11, 11, 11, 11, 11, 11, 11, 11, 1, 1, 1, 12, 1, 1,
+    1, 13, 13, 13, 13, 13, 13, 13, 13, 13, 13, 13, 13, 13, 13, 13,
+    13, 13, 13, 13, 13, 13, 13, 13, 13, 13, 13, 1, 1, 1, 1, 13,
+    1, 13, 13, 13, 13, 14, 13, 13, 13, 13, 13, 13, 15, 13, 13, 13,
+    13, 13, 13, 13, 16, 13, 13, 13, 13, 13, 13, 1, 1, 1, 1, 1,
+];
+
+// The classes of the other chars, as sorted ranges of code points.
+static CLASS_RANGES: [(u32, u32, u8); 1] = [
+    (0x80, 0x10ffff, 1),
+];
+
+// The next state of every state on every class, one row per state.
+// State 0 has no transitions, and is where missing transitions lead.
+static TRANSITIONS: [u8; 323] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0,
+    0, 0, 2, 2, 3, 4, 5, 6, 7, 0, 8, 9, 10, 11, 11, 12,
+    11,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0,
+    0, 0, 0, 0, 0, 0, 13, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 14, 0, 0, 0, 0, 0,
+    0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 15, 0, 9, 0, 0, 0, 0,
+    0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 11, 0, 11, 11, 11,
+    11,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 11, 0, 11, 16, 11,
+    11,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0,
+    0, 14, 14, 0, 14, 14, 14, 14, 14, 14, 14, 14, 14, 14, 14, 14,
+    14,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 17, 0, 0, 0, 0,
+    0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 11, 0, 11, 11, 11,
+    18,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 17, 0, 0, 0, 0,
+    0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 11, 0, 11, 11, 11,
+    11,
+];
+
+// One more than the rule every state accepts, or 0.
+static ACCEPT: [u8; 19] = [
+    0, 0, 12, 9, 10, 6, 4, 5, 7, 3, 8, 2, 2, 13, 11, 0,
+    2, 3, 1,
+];
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer { input, offset: 0 }
+    }
+
+    /// The byte offset at which the next lexeme starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn class_of(ch: char) -> usize {
+        let code = ch as u32;
+
+        if code < 128 {
+            return ASCII_CLASSES[code as usize] as usize;
+        }
+
+        let found = CLASS_RANGES.binary_search_by(|&(from, to, _)| {
+            if to < code {
+                std::cmp::Ordering::Less
+            } else if from > code {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        });
+
+        match found {
+            Ok(index) => CLASS_RANGES[index].2 as usize,
+            Err(_) => 0,
+        }
+    }
+
+    /// The length and rule of the longest non-empty match at the offset.
+    fn longest_match(&self) -> Option<(usize, usize)> {
+        let mut state = START;
+        let mut last_accept = None;
+
+        for (index, ch) in self.input[self.offset..].char_indices() {
+            state = TRANSITIONS[state * CLASSES + Lexer::class_of(ch)] as usize;
+
+            if state == 0 {
+                break;
+            }
+
+            if ACCEPT[state] != 0 {
+                last_accept = Some((index + ch.len_utf8(), ACCEPT[state] as usize - 1));
+            }
+        }
+
+        last_accept
+    }
+
+    /// The token to return for `text`, matched by `rule`, if any.
+    #[allow(unused_variables)]
+    fn action(&mut self, rule: usize, text: &'a str) -> Option<Token> {
+        match rule {
+            0 => Some(Token::Let),
+            1 => Some(Token::Ident),
+            2 => Some(Token::Number),
+            3 => Some(Token::Plus),
+            4 => Some(Token::Minus),
+            5 => Some(Token::Star),
+            6 => Some(Token::Slash),
+            7 => Some(Token::Eq),
+            8 => Some(Token::LParen),
+            9 => Some(Token::RParen),
+            10 => None,
+            11 => {
+                None
+            }
+            12 => {
+                // `**` is spelled out as two stars.
+                let _ = text;
+                Some(Token::Star)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Lexeme<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.offset;
+            let ch = self.input[start..].chars().next()?;
+
+            match self.longest_match() {
+                Some((length, rule)) => {
+                    self.offset += length;
+
+                    let text = &self.input[start..self.offset];
+
+                    if let Some(token) = self.action(rule, text) {
+                        let end = self.offset;
+
+                        return Some(Ok(Lexeme { token, text, start, end }));
+                    }
+                }
+                None => {
+                    self.offset += ch.len_utf8();
+
+                    return Some(Err(LexError { offset: start, ch }));
+                }
+            }
+        }
+    }
+}
+
+impl Token {
+    pub fn is_operator(self) -> bool {
+        matches!(self, Token::Plus | Token::Minus | Token::Star | Token::Slash)
+    }
+}
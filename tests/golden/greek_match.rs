@@ -0,0 +1,122 @@
+// Generated by rlex. Do not edit.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Word,
+    Name,
+}
+
+/// A token with the text it was matched from and the byte offsets of the
+/// text in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lexeme<'a> {
+    pub token: Token,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A char at which no rule matches, with its byte offset in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub offset: usize,
+    pub ch: char,
+}
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer { input, offset: 0 }
+    }
+
+    /// The byte offset at which the next lexeme starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The length and rule of the longest non-empty match at the offset.
+    fn longest_match(&self) -> Option<(usize, usize)> {
+        let mut state = 0;
+        let mut last_accept = None;
+
+        for (index, ch) in self.input[self.offset..].char_indices() {
+            state = match state {
+                0 => match ch {
+                    ' ' => 1,
+                    'Α'..='Ω' => 2,
+                    'α'..='ω' => 3,
+                    _ => break,
+                },
+                1 => match ch {
+                    ' ' => 1,
+                    _ => break,
+                },
+                2 => match ch {
+                    'α'..='ω' => 2,
+                    _ => break,
+                },
+                3 => match ch {
+                    'α'..='ω' => 3,
+                    _ => break,
+                },
+                _ => unreachable!(),
+            };
+
+            let rule = match state {
+                3 => 0,
+                2 => 1,
+                1 => 2,
+                _ => continue,
+            };
+
+            last_accept = Some((index + ch.len_utf8(), rule));
+        }
+
+        last_accept
+    }
+
+    /// The token to return for `text`, matched by `rule`, if any.
+    #[allow(unused_variables)]
+    fn action(&mut self, rule: usize, text: &'a str) -> Option<Token> {
+        match rule {
+            0 => Some(Token::Word),
+            1 => Some(Token::Name),
+            2 => None,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Lexeme<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.offset;
+            let ch = self.input[start..].chars().next()?;
+
+            match self.longest_match() {
+                Some((length, rule)) => {
+                    self.offset += length;
+
+                    let text = &self.input[start..self.offset];
+
+                    if let Some(token) = self.action(rule, text) {
+                        let end = self.offset;
+
+                        return Some(Ok(Lexeme { token, text, start, end }));
+                    }
+                }
+                None => {
+                    self.offset += ch.len_utf8();
+
+                    return Some(Err(LexError { offset: start, ch }));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+// Generated by rlex. Do not edit.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Section,
+    LastWord,
+    Word,
+    Eof,
+    Value,
+    Unterminated,
+}
+
+/// A token with the text it was matched from and the byte offsets of the
+/// text in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lexeme<'a> {
+    pub token: Token,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A char at which no rule matches, with its byte offset in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub offset: usize,
+    pub ch: char,
+}
+
+/// The modes of the lexer, which decide the rules it matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Initial,
+    Value,
+}
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    offset: usize,
+    modes: Vec<Mode>,
+    ended: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer {
+            input,
+            offset: 0,
+            modes: vec![Mode::Initial],
+            ended: false,
+        }
+    }
+
+    /// The mode on top of the mode stack.
+    pub fn mode(&self) -> Mode {
+        *self.modes.last().unwrap()
+    }
+
+    /// Switches to `mode` until it is popped.
+    pub fn push_mode(&mut self, mode: Mode) {
+        self.modes.push(mode);
+    }
+
+    /// Pops the mode on top of the stack and returns it, unless it is the
+    /// last one.
+    pub fn pop_mode(&mut self) -> Option<Mode> {
+        if self.modes.len() > 1 {
+            self.modes.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the mode on top of the stack.
+    pub fn set_mode(&mut self, mode: Mode) {
+        *self.modes.last_mut().unwrap() = mode;
+    }
+
+    /// The byte offset at which the next lexeme starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Whether the next lexeme starts a line.
+    fn at_line_start(&self) -> bool {
+        self.offset == 0 || self.input.as_bytes()[self.offset - 1] == b'\n'
+    }
+
+    /// The length and rule of the longest non-empty match at the offset.
+    fn longest_match(&self) -> Option<(usize, usize)> {
+        let mut state = match (self.mode(), self.at_line_start()) {
+            (Mode::Initial, false) => 0,
+            (Mode::Initial, true) => 2,
+            (Mode::Value, _) => 1,
+        };
+        let mut last_accept = None;
+
+        for (index, ch) in self.input[self.offset..].char_indices() {
+            state = match state {
+                0 => match ch {
+                    '\n' | ' ' => 3,
+                    '=' => 4,
+                    'a'..='z' => 5,
+                    _ => break,
+                },
+                1 => match ch {
+                    '\0'..='\t' | '\u{b}'..='\u{10ffff}' => 6,
+                    '\n' => 7,
+                },
+                2 => match ch {
+                    '\n' | ' ' => 3,
+                    '=' => 4,
+                    'a'..='z' => 5,
+                    '#' => 8,
+                    '[' => 9,
+                    _ => break,
+                },
+                5 => match ch {
+                    'a'..='z' => 5,
+                    '\n' => 10,
+                    _ => break,
+                },
+                6 => match ch {
+                    '\0'..='\t' | '\u{b}'..='\u{10ffff}' => 6,
+                    _ => break,
+                },
+                8 => match ch {
+                    '\0'..='\t' | '\u{b}'..='\u{10ffff}' => 8,
+                    _ => break,
+                },
+                9 => match ch {
+                    'a'..='z' => 11,
+                    _ => break,
+                },
+                11 => match ch {
+                    'a'..='z' => 11,
+                    ']' => 12,
+                    _ => break,
+                },
+                3 | 4 | 7 | 10 | 12 => break,
+                _ => unreachable!(),
+            };
+
+            let rule = match state {
+                8 => 0,
+                12 => 1,
+                10 => 2,
+                5 => 3,
+                4 => 4,
+                3 => 5,
+                6 => 7,
+                7 => 8,
+                _ => continue,
+            };
+
+            last_accept = Some((index + ch.len_utf8(), rule));
+        }
+
+        last_accept
+    }
+
+    /// The length of the token in the `length` bytes at the offset that
+    /// `rule` matched, without its trailing context.
+    fn token_len(&self, length: usize, rule: usize) -> usize {
+        let text = &self.input[self.offset..self.offset + length];
+
+        match rule {
+            2 => text.char_indices().rev().nth(0).map_or(0, |(index, _)| index),
+            _ => length,
+        }
+    }
+
+    /// The lexeme of the end of the input, the first time it runs out.
+    fn end_of_input(&mut self) -> Option<Result<Lexeme<'a>, LexError>> {
+        if self.ended {
+            return None;
+        }
+
+        self.ended = true;
+
+        let rule = match self.mode() {
+            Mode::Initial => 6,
+            Mode::Value => 9,
+        };
+
+        let token = self.action(rule, "")?;
+        let start = self.offset;
+
+        Some(Ok(Lexeme { token, text: "", start, end: start }))
+    }
+
+    /// The token to return for `text`, matched by `rule`, if any.
+    #[allow(unused_variables)]
+    fn action(&mut self, rule: usize, text: &'a str) -> Option<Token> {
+        match rule {
+            0 => None,
+            1 => Some(Token::Section),
+            2 => Some(Token::LastWord),
+            3 => Some(Token::Word),
+            4 => {
+                self.push_mode(Mode::Value);
+                None
+            }
+            5 => None,
+            6 => Some(Token::Eof),
+            7 => Some(Token::Value),
+            8 => {
+                self.pop_mode();
+                None
+            }
+            9 => Some(Token::Unterminated),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Lexeme<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.offset;
+            let ch = match self.input[start..].chars().next() {
+                Some(ch) => ch,
+                None => return self.end_of_input(),
+            };
+
+            match self.longest_match() {
+                Some((length, rule)) => {
+                    self.offset += self.token_len(length, rule);
+
+                    let text = &self.input[start..self.offset];
+
+                    if let Some(token) = self.action(rule, text) {
+                        let end = self.offset;
+
+                        return Some(Ok(Lexeme { token, text, start, end }));
+                    }
+                }
+                None => {
+                    self.offset += ch.len_utf8();
+
+                    return Some(Err(LexError { offset: start, ch }));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,266 @@
+// Generated by rlex. Do not edit.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Section,
+    LastWord,
+    Word,
+    Eof,
+    Value,
+    Unterminated,
+}
+
+/// A token with the text it was matched from and the byte offsets of the
+/// text in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lexeme<'a> {
+    pub token: Token,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A char at which no rule matches, with its byte offset in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub offset: usize,
+    pub ch: char,
+}
+
+/// The modes of the lexer, which decide the rules it matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Initial,
+    Value,
+}
+
+// The start state of every mode, then at the start of a line.
+static STARTS: [usize; 4] = [
+    1, 2, 3, 2,
+];
+
+const CLASSES: usize = 9;
+
+// The class of every ASCII char.
+static ASCII_CLASSES: [u8; 128] = [
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    3, 1, 1, 4, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 5, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 6, 1, 7, 1, 1,
+    1, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8,
+    8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 1, 1, 1, 1,
+];
+
+// The classes of the other chars, as sorted ranges of code points.
+static CLASS_RANGES: [(u32, u32, u8); 1] = [
+    (0x80, 0x10ffff, 1),
+];
+
+// The next state of every state on every class, one row per state.
+// State 0 has no transitions, and is where missing transitions lead.
+static TRANSITIONS: [u8; 126] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 4, 4, 0, 5, 0, 0, 6,
+    0, 7, 8, 7, 7, 7, 7, 7, 7,
+    0, 0, 4, 4, 9, 5, 10, 0, 6,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 11, 0, 0, 0, 0, 0, 6,
+    0, 7, 0, 7, 7, 7, 7, 7, 7,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 9, 0, 9, 9, 9, 9, 9, 9,
+    0, 0, 0, 0, 0, 0, 0, 0, 12,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 13, 12,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+// One more than the rule every state accepts, or 0.
+static ACCEPT: [u8; 14] = [
+    0, 0, 0, 0, 6, 5, 4, 8, 9, 1, 0, 3, 0, 2,
+];
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    offset: usize,
+    modes: Vec<Mode>,
+    ended: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer {
+            input,
+            offset: 0,
+            modes: vec![Mode::Initial],
+            ended: false,
+        }
+    }
+
+    /// The mode on top of the mode stack.
+    pub fn mode(&self) -> Mode {
+        *self.modes.last().unwrap()
+    }
+
+    /// Switches to `mode` until it is popped.
+    pub fn push_mode(&mut self, mode: Mode) {
+        self.modes.push(mode);
+    }
+
+    /// Pops the mode on top of the stack and returns it, unless it is the
+    /// last one.
+    pub fn pop_mode(&mut self) -> Option<Mode> {
+        if self.modes.len() > 1 {
+            self.modes.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the mode on top of the stack.
+    pub fn set_mode(&mut self, mode: Mode) {
+        *self.modes.last_mut().unwrap() = mode;
+    }
+
+    /// The byte offset at which the next lexeme starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Whether the next lexeme starts a line.
+    fn at_line_start(&self) -> bool {
+        self.offset == 0 || self.input.as_bytes()[self.offset - 1] == b'\n'
+    }
+
+    fn class_of(ch: char) -> usize {
+        let code = ch as u32;
+
+        if code < 128 {
+            return ASCII_CLASSES[code as usize] as usize;
+        }
+
+        let found = CLASS_RANGES.binary_search_by(|&(from, to, _)| {
+            if to < code {
+                std::cmp::Ordering::Less
+            } else if from > code {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        });
+
+        match found {
+            Ok(index) => CLASS_RANGES[index].2 as usize,
+            Err(_) => 0,
+        }
+    }
+
+    /// The length and rule of the longest non-empty match at the offset.
+    fn longest_match(&self) -> Option<(usize, usize)> {
+        let mut state = STARTS[self.mode() as usize + 2 * self.at_line_start() as usize];
+        let mut last_accept = None;
+
+        for (index, ch) in self.input[self.offset..].char_indices() {
+            state = TRANSITIONS[state * CLASSES + Lexer::class_of(ch)] as usize;
+
+            if state == 0 {
+                break;
+            }
+
+            if ACCEPT[state] != 0 {
+                last_accept = Some((index + ch.len_utf8(), ACCEPT[state] as usize - 1));
+            }
+        }
+
+        last_accept
+    }
+
+    /// The length of the token in the `length` bytes at the offset that
+    /// `rule` matched, without its trailing context.
+    fn token_len(&self, length: usize, rule: usize) -> usize {
+        let text = &self.input[self.offset..self.offset + length];
+
+        match rule {
+            2 => text.char_indices().rev().nth(0).map_or(0, |(index, _)| index),
+            _ => length,
+        }
+    }
+
+    /// The lexeme of the end of the input, the first time it runs out.
+    fn end_of_input(&mut self) -> Option<Result<Lexeme<'a>, LexError>> {
+        if self.ended {
+            return None;
+        }
+
+        self.ended = true;
+
+        let rule = match self.mode() {
+            Mode::Initial => 6,
+            Mode::Value => 9,
+        };
+
+        let token = self.action(rule, "")?;
+        let start = self.offset;
+
+        Some(Ok(Lexeme { token, text: "", start, end: start }))
+    }
+
+    /// The token to return for `text`, matched by `rule`, if any.
+    #[allow(unused_variables)]
+    fn action(&mut self, rule: usize, text: &'a str) -> Option<Token> {
+        match rule {
+            0 => None,
+            1 => Some(Token::Section),
+            2 => Some(Token::LastWord),
+            3 => Some(Token::Word),
+            4 => {
+                self.push_mode(Mode::Value);
+                None
+            }
+            5 => None,
+            6 => Some(Token::Eof),
+            7 => Some(Token::Value),
+            8 => {
+                self.pop_mode();
+                None
+            }
+            9 => Some(Token::Unterminated),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Lexeme<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.offset;
+            let ch = match self.input[start..].chars().next() {
+                Some(ch) => ch,
+                None => return self.end_of_input(),
+            };
+
+            match self.longest_match() {
+                Some((length, rule)) => {
+                    self.offset += self.token_len(length, rule);
+
+                    let text = &self.input[start..self.offset];
+
+                    if let Some(token) = self.action(rule, text) {
+                        let end = self.offset;
+
+                        return Some(Ok(Lexeme { token, text, start, end }));
+                    }
+                }
+                None => {
+                    self.offset += ch.len_utf8();
+
+                    return Some(Err(LexError { offset: start, ch }));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// Generated by rlex. Do not edit.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Int,
+    Float,
+    Range,
+    If,
+    Ident,
+    LParen,
+    RParen,
+}
+
+/// A token with the text it was matched from and the byte offsets of the
+/// text in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lexeme<'a> {
+    pub token: Token,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A char at which no rule matches, with its byte offset in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub offset: usize,
+    pub ch: char,
+}
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer { input, offset: 0 }
+    }
+
+    /// The byte offset at which the next lexeme starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The length and rule of the longest non-empty match at the offset.
+    fn longest_match(&self) -> Option<(usize, usize)> {
+        let mut state = 0;
+        let mut last_accept = None;
+
+        for (index, ch) in self.input[self.offset..].char_indices() {
+            state = match state {
+                0 => match ch {
+                    ' ' => 1,
+                    '(' => 2,
+                    ')' => 3,
+                    '.' => 4,
+                    '0'..='9' => 5,
+                    'a'..='h' | 'j'..='z' => 6,
+                    'i' => 7,
+                    _ => break,
+                },
+                1 => match ch {
+                    ' ' => 1,
+                    _ => break,
+                },
+                4 => match ch {
+                    '.' => 8,
+                    _ => break,
+                },
+                5 => match ch {
+                    '0'..='9' => 5,
+                    '.' => 9,
+                    _ => break,
+                },
+                6 => match ch {
+                    'a'..='z' => 6,
+                    _ => break,
+                },
+                7 => match ch {
+                    'a'..='e' | 'g'..='z' => 6,
+                    'f' => 10,
+                    _ => break,
+                },
+                9 => match ch {
+                    '.' => 11,
+                    '0'..='9' => 12,
+                    _ => break,
+                },
+                10 => match ch {
+                    'a'..='z' => 6,
+                    ' ' => 13,
+                    '(' => 14,
+                    _ => break,
+                },
+                12 => match ch {
+                    '0'..='9' => 12,
+                    _ => break,
+                },
+                13 => match ch {
+                    ' ' => 13,
+                    '(' => 14,
+                    _ => break,
+                },
+                2 | 3 | 8 | 11 | 14 => break,
+                _ => unreachable!(),
+            };
+
+            let rule = match state {
+                11 => 0,
+                12 => 1,
+                5 => 2,
+                8 => 3,
+                14 => 4,
+                6 | 7 | 10 => 5,
+                2 => 6,
+                3 => 7,
+                1 => 8,
+                _ => continue,
+            };
+
+            last_accept = Some((index + ch.len_utf8(), rule));
+        }
+
+        last_accept
+    }
+
+    /// The length of the token in the `length` bytes at the offset that
+    /// `rule` matched, without its trailing context.
+    fn token_len(&self, length: usize, rule: usize) -> usize {
+        let text = &self.input[self.offset..self.offset + length];
+
+        match rule {
+            0 => text.char_indices().rev().nth(1).map_or(0, |(index, _)| index),
+            4 => text.char_indices().nth(2).map_or(length, |(index, _)| index),
+            _ => length,
+        }
+    }
+
+    /// The token to return for `text`, matched by `rule`, if any.
+    #[allow(unused_variables)]
+    fn action(&mut self, rule: usize, text: &'a str) -> Option<Token> {
+        match rule {
+            0 => Some(Token::Int),
+            1 => Some(Token::Float),
+            2 => Some(Token::Int),
+            3 => Some(Token::Range),
+            4 => Some(Token::If),
+            5 => Some(Token::Ident),
+            6 => Some(Token::LParen),
+            7 => Some(Token::RParen),
+            8 => None,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Lexeme<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.offset;
+            let ch = self.input[start..].chars().next()?;
+
+            match self.longest_match() {
+                Some((length, rule)) => {
+                    self.offset += self.token_len(length, rule);
+
+                    let text = &self.input[start..self.offset];
+
+                    if let Some(token) = self.action(rule, text) {
+                        let end = self.offset;
+
+                        return Some(Ok(Lexeme { token, text, start, end }));
+                    }
+                }
+                None => {
+                    self.offset += ch.len_utf8();
+
+                    return Some(Err(LexError { offset: start, ch }));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// Generated by rlex. Do not edit.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Int,
+    Float,
+    Range,
+    If,
+    Ident,
+    LParen,
+    RParen,
+}
+
+/// A token with the text it was matched from and the byte offsets of the
+/// text in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lexeme<'a> {
+    pub token: Token,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A char at which no rule matches, with its byte offset in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub offset: usize,
+    pub ch: char,
+}
+
+const START: usize = 1;
+const CLASSES: usize = 9;
+
+// The class of every ASCII char.
+static ASCII_CLASSES: [u8; 128] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 0, 0, 0, 0, 0, 0, 0, 2, 3, 0, 0, 0, 0, 4, 0,
+    5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 6, 6, 6, 6, 6, 7, 6, 6, 8, 6, 6, 6, 6, 6, 6,
+    6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 0, 0, 0, 0, 0,
+];
+
+// The classes of the other chars, as sorted ranges of code points.
+static CLASS_RANGES: [(u32, u32, u8); 0] = [
+];
+
+// The next state of every state on every class, one row per state.
+// State 0 has no transitions, and is where missing transitions lead.
+static TRANSITIONS: [u8; 144] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 2, 3, 4, 5, 6, 7, 7, 8,
+    0, 2, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 9, 0, 0, 0, 0,
+    0, 0, 0, 0, 10, 6, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 7, 7, 7,
+    0, 0, 0, 0, 0, 0, 7, 11, 7,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 12, 13, 0, 0, 0,
+    0, 14, 15, 0, 0, 0, 7, 7, 7,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 13, 0, 0, 0,
+    0, 14, 15, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+// One more than the rule every state accepts, or 0.
+static ACCEPT: [u8; 16] = [
+    0, 0, 9, 7, 8, 0, 3, 6, 6, 4, 0, 6, 1, 2, 0, 5,
+];
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer { input, offset: 0 }
+    }
+
+    /// The byte offset at which the next lexeme starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn class_of(ch: char) -> usize {
+        let code = ch as u32;
+
+        if code < 128 {
+            return ASCII_CLASSES[code as usize] as usize;
+        }
+
+        let found = CLASS_RANGES.binary_search_by(|&(from, to, _)| {
+            if to < code {
+                std::cmp::Ordering::Less
+            } else if from > code {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        });
+
+        match found {
+            Ok(index) => CLASS_RANGES[index].2 as usize,
+            Err(_) => 0,
+        }
+    }
+
+    /// The length and rule of the longest non-empty match at the offset.
+    fn longest_match(&self) -> Option<(usize, usize)> {
+        let mut state = START;
+        let mut last_accept = None;
+
+        for (index, ch) in self.input[self.offset..].char_indices() {
+            state = TRANSITIONS[state * CLASSES + Lexer::class_of(ch)] as usize;
+
+            if state == 0 {
+                break;
+            }
+
+            if ACCEPT[state] != 0 {
+                last_accept = Some((index + ch.len_utf8(), ACCEPT[state] as usize - 1));
+            }
+        }
+
+        last_accept
+    }
+
+    /// The length of the token in the `length` bytes at the offset that
+    /// `rule` matched, without its trailing context.
+    fn token_len(&self, length: usize, rule: usize) -> usize {
+        let text = &self.input[self.offset..self.offset + length];
+
+        match rule {
+            0 => text.char_indices().rev().nth(1).map_or(0, |(index, _)| index),
+            4 => text.char_indices().nth(2).map_or(length, |(index, _)| index),
+            _ => length,
+        }
+    }
+
+    /// The token to return for `text`, matched by `rule`, if any.
+    #[allow(unused_variables)]
+    fn action(&mut self, rule: usize, text: &'a str) -> Option<Token> {
+        match rule {
+            0 => Some(Token::Int),
+            1 => Some(Token::Float),
+            2 => Some(Token::Int),
+            3 => Some(Token::Range),
+            4 => Some(Token::If),
+            5 => Some(Token::Ident),
+            6 => Some(Token::LParen),
+            7 => Some(Token::RParen),
+            8 => None,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Lexeme<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.offset;
+            let ch = self.input[start..].chars().next()?;
+
+            match self.longest_match() {
+                Some((length, rule)) => {
+                    self.offset += self.token_len(length, rule);
+
+                    let text = &self.input[start..self.offset];
+
+                    if let Some(token) = self.action(rule, text) {
+                        let end = self.offset;
+
+                        return Some(Ok(Lexeme { token, text, start, end }));
+                    }
+                }
+                None => {
+                    self.offset += ch.len_utf8();
+
+                    return Some(Err(LexError { offset: start, ch }));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+// Generated by rlex. Do not edit.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Ident,
+    Text,
+    Escape,
+}
+
+/// A token with the text it was matched from and the byte offsets of the
+/// text in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lexeme<'a> {
+    pub token: Token,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A char at which no rule matches, with its byte offset in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub offset: usize,
+    pub ch: char,
+}
+
+/// The modes of the lexer, which decide the rules it matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Initial,
+    Str,
+    BlockComment,
+    LineComment,
+}
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    offset: usize,
+    modes: Vec<Mode>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer {
+            input,
+            offset: 0,
+            modes: vec![Mode::Initial],
+        }
+    }
+
+    /// The mode on top of the mode stack.
+    pub fn mode(&self) -> Mode {
+        *self.modes.last().unwrap()
+    }
+
+    /// Switches to `mode` until it is popped.
+    pub fn push_mode(&mut self, mode: Mode) {
+        self.modes.push(mode);
+    }
+
+    /// Pops the mode on top of the stack and returns it, unless it is the
+    /// last one.
+    pub fn pop_mode(&mut self) -> Option<Mode> {
+        if self.modes.len() > 1 {
+            self.modes.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the mode on top of the stack.
+    pub fn set_mode(&mut self, mode: Mode) {
+        *self.modes.last_mut().unwrap() = mode;
+    }
+
+    /// The byte offset at which the next lexeme starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The length and rule of the longest non-empty match at the offset.
+    fn longest_match(&self) -> Option<(usize, usize)> {
+        let mut state = match self.mode() {
+            Mode::Initial => 0,
+            Mode::Str => 1,
+            Mode::BlockComment => 2,
+            Mode::LineComment => 3,
+        };
+        let mut last_accept = None;
+
+        for (index, ch) in self.input[self.offset..].char_indices() {
+            state = match state {
+                0 => match ch {
+                    '\n' | ' ' => 4,
+                    '"' => 5,
+                    '/' => 6,
+                    'a'..='z' => 7,
+                    _ => break,
+                },
+                1 => match ch {
+                    '\0'..='\t' | '\u{b}'..='!' | '#'..='[' | ']'..='\u{10ffff}' => 8,
+                    '"' => 9,
+                    '\\' => 10,
+                    _ => break,
+                },
+                2 => match ch {
+                    '\0'..=')' | '+'..='.' | '0'..='\u{10ffff}' => 11,
+                    '*' => 12,
+                    '/' => 13,
+                },
+                3 => match ch {
+                    '\0'..='\t' | '\u{b}'..='\u{10ffff}' => 14,
+                    '\n' => 15,
+                },
+                6 => match ch {
+                    '*' => 16,
+                    '/' => 17,
+                    _ => break,
+                },
+                7 => match ch {
+                    'a'..='z' => 7,
+                    _ => break,
+                },
+                8 => match ch {
+                    '\0'..='\t' | '\u{b}'..='!' | '#'..='[' | ']'..='\u{10ffff}' => 8,
+                    _ => break,
+                },
+                10 => match ch {
+                    '\0'..='\t' | '\u{b}'..='\u{10ffff}' => 18,
+                    _ => break,
+                },
+                11 => match ch {
+                    '\0'..=')' | '+'..='.' | '0'..='\u{10ffff}' => 11,
+                    _ => break,
+                },
+                12 => match ch {
+                    '/' => 19,
+                    _ => break,
+                },
+                13 => match ch {
+                    '*' => 16,
+                    _ => break,
+                },
+                14 => match ch {
+                    '\0'..='\t' | '\u{b}'..='\u{10ffff}' => 14,
+                    _ => break,
+                },
+                4 | 5 | 9 | 15..=19 => break,
+                _ => unreachable!(),
+            };
+
+            let rule = match state {
+                7 => 0,
+                5 => 1,
+                4 => 2,
+                17 => 3,
+                16 => 4,
+                19 => 5,
+                11..=13 => 6,
+                14 => 7,
+                15 => 8,
+                8 => 9,
+                18 => 10,
+                9 => 11,
+                _ => continue,
+            };
+
+            last_accept = Some((index + ch.len_utf8(), rule));
+        }
+
+        last_accept
+    }
+
+    /// The token to return for `text`, matched by `rule`, if any.
+    #[allow(unused_variables)]
+    fn action(&mut self, rule: usize, text: &'a str) -> Option<Token> {
+        match rule {
+            0 => Some(Token::Ident),
+            1 => {
+                self.push_mode(Mode::Str);
+                None
+            }
+            2 => None,
+            3 => {
+                self.set_mode(Mode::LineComment);
+                None
+            }
+            4 => {
+                self.push_mode(Mode::BlockComment);
+                None
+            }
+            5 => {
+                self.pop_mode();
+                None
+            }
+            6 => None,
+            7 => None,
+            8 => {
+                self.set_mode(Mode::Initial);
+                None
+            }
+            9 => Some(Token::Text),
+            10 => Some(Token::Escape),
+            11 => {
+                self.pop_mode();
+                None
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Lexeme<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.offset;
+            let ch = self.input[start..].chars().next()?;
+
+            match self.longest_match() {
+                Some((length, rule)) => {
+                    self.offset += length;
+
+                    let text = &self.input[start..self.offset];
+
+                    if let Some(token) = self.action(rule, text) {
+                        let end = self.offset;
+
+                        return Some(Ok(Lexeme { token, text, start, end }));
+                    }
+                }
+                None => {
+                    self.offset += ch.len_utf8();
+
+                    return Some(Err(LexError { offset: start, ch }));
+                }
+            }
+        }
+    }
+}
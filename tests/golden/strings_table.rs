@@ -0,0 +1,247 @@
+// Generated by rlex. Do not edit.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Ident,
+    Text,
+    Escape,
+}
+
+/// A token with the text it was matched from and the byte offsets of the
+/// text in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lexeme<'a> {
+    pub token: Token,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A char at which no rule matches, with its byte offset in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub offset: usize,
+    pub ch: char,
+}
+
+/// The modes of the lexer, which decide the rules it matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Initial,
+    Str,
+    BlockComment,
+    LineComment,
+}
+
+// The start state of every mode.
+static STARTS: [usize; 4] = [
+    1, 2, 3, 4,
+];
+
+const CLASSES: usize = 9;
+
+// The class of every ASCII char.
+static ASCII_CLASSES: [u8; 128] = [
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    3, 1, 4, 1, 1, 1, 1, 1, 1, 1, 5, 1, 1, 1, 1, 6,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 7, 1, 1, 1,
+    1, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8,
+    8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 1, 1, 1, 1,
+];
+
+// The classes of the other chars, as sorted ranges of code points.
+static CLASS_RANGES: [(u32, u32, u8); 1] = [
+    (0x80, 0x10ffff, 1),
+];
+
+// The next state of every state on every class, one row per state.
+// State 0 has no transitions, and is where missing transitions lead.
+static TRANSITIONS: [u8; 189] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 5, 5, 6, 0, 7, 0, 8,
+    0, 9, 0, 9, 10, 9, 9, 11, 9,
+    0, 12, 12, 12, 12, 13, 14, 12, 12,
+    0, 15, 16, 15, 15, 15, 15, 15, 15,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 17, 18, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 8,
+    0, 9, 0, 9, 0, 9, 9, 0, 9,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 19, 0, 19, 19, 19, 19, 19, 19,
+    0, 12, 12, 12, 12, 0, 0, 12, 12,
+    0, 0, 0, 0, 0, 0, 20, 0, 0,
+    0, 0, 0, 0, 0, 17, 0, 0, 0,
+    0, 15, 0, 15, 15, 15, 15, 15, 15,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+// One more than the rule every state accepts, or 0.
+static ACCEPT: [u8; 21] = [
+    0, 0, 0, 0, 0, 3, 2, 0, 1, 10, 12, 0, 7, 7, 7, 8,
+    9, 5, 4, 11, 6,
+];
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    offset: usize,
+    modes: Vec<Mode>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer {
+            input,
+            offset: 0,
+            modes: vec![Mode::Initial],
+        }
+    }
+
+    /// The mode on top of the mode stack.
+    pub fn mode(&self) -> Mode {
+        *self.modes.last().unwrap()
+    }
+
+    /// Switches to `mode` until it is popped.
+    pub fn push_mode(&mut self, mode: Mode) {
+        self.modes.push(mode);
+    }
+
+    /// Pops the mode on top of the stack and returns it, unless it is the
+    /// last one.
+    pub fn pop_mode(&mut self) -> Option<Mode> {
+        if self.modes.len() > 1 {
+            self.modes.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the mode on top of the stack.
+    pub fn set_mode(&mut self, mode: Mode) {
+        *self.modes.last_mut().unwrap() = mode;
+    }
+
+    /// The byte offset at which the next lexeme starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn class_of(ch: char) -> usize {
+        let code = ch as u32;
+
+        if code < 128 {
+            return ASCII_CLASSES[code as usize] as usize;
+        }
+
+        let found = CLASS_RANGES.binary_search_by(|&(from, to, _)| {
+            if to < code {
+                std::cmp::Ordering::Less
+            } else if from > code {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        });
+
+        match found {
+            Ok(index) => CLASS_RANGES[index].2 as usize,
+            Err(_) => 0,
+        }
+    }
+
+    /// The length and rule of the longest non-empty match at the offset.
+    fn longest_match(&self) -> Option<(usize, usize)> {
+        let mut state = STARTS[self.mode() as usize];
+        let mut last_accept = None;
+
+        for (index, ch) in self.input[self.offset..].char_indices() {
+            state = TRANSITIONS[state * CLASSES + Lexer::class_of(ch)] as usize;
+
+            if state == 0 {
+                break;
+            }
+
+            if ACCEPT[state] != 0 {
+                last_accept = Some((index + ch.len_utf8(), ACCEPT[state] as usize - 1));
+            }
+        }
+
+        last_accept
+    }
+
+    /// The token to return for `text`, matched by `rule`, if any.
+    #[allow(unused_variables)]
+    fn action(&mut self, rule: usize, text: &'a str) -> Option<Token> {
+        match rule {
+            0 => Some(Token::Ident),
+            1 => {
+                self.push_mode(Mode::Str);
+                None
+            }
+            2 => None,
+            3 => {
+                self.set_mode(Mode::LineComment);
+                None
+            }
+            4 => {
+                self.push_mode(Mode::BlockComment);
+                None
+            }
+            5 => {
+                self.pop_mode();
+                None
+            }
+            6 => None,
+            7 => None,
+            8 => {
+                self.set_mode(Mode::Initial);
+                None
+            }
+            9 => Some(Token::Text),
+            10 => Some(Token::Escape),
+            11 => {
+                self.pop_mode();
+                None
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Lexeme<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.offset;
+            let ch = self.input[start..].chars().next()?;
+
+            match self.longest_match() {
+                Some((length, rule)) => {
+                    self.offset += length;
+
+                    let text = &self.input[start..self.offset];
+
+                    if let Some(token) = self.action(rule, text) {
+                        let end = self.offset;
+
+                        return Some(Ok(Lexeme { token, text, start, end }));
+                    }
+                }
+                None => {
+                    self.offset += ch.len_utf8();
+
+                    return Some(Err(LexError { offset: start, ch }));
+                }
+            }
+        }
+    }
+}